@@ -0,0 +1,72 @@
+use std::hint::black_box;
+
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use snake::{
+    arena::ArenaSize,
+    occupancy::{Occupancy, Occupant},
+};
+
+// square arenas with every other row taken by a snake's body
+const SIZES: [i32; 3] = [100, 200, 400];
+
+fn filled(size: i32) -> (ArenaSize, Vec<IVec3>) {
+    let arena = ArenaSize::new(size, size);
+    let cells = arena
+        .cells()
+        .filter(|cell| cell.z.rem_euclid(2) == 0)
+        .collect();
+    (arena, cells)
+}
+
+fn occupancy(cells: &[IVec3]) -> Occupancy {
+    let mut occupancy = Occupancy::default();
+    let head = Entity::from_raw(0);
+    for (i, cell) in cells.iter().enumerate() {
+        occupancy.insert(
+            Entity::from_raw(i as u32 + 1),
+            *cell,
+            Occupant::SnakeBody { head },
+        );
+    }
+    occupancy
+}
+
+// a collision test against the grid, the same cost whatever the arena size
+fn lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lookup");
+    for size in SIZES {
+        let (arena, cells) = filled(size);
+        let occupancy = occupancy(&cells);
+        let probe = arena.max();
+        group.bench_with_input(BenchmarkId::new("occupancy", size), &probe, |b, probe| {
+            b.iter(|| occupancy.is_solid(black_box(*probe)))
+        });
+        group.bench_with_input(BenchmarkId::new("linear_scan", size), &probe, |b, probe| {
+            b.iter(|| cells.iter().any(|cell| cell == black_box(probe)))
+        });
+    }
+    group.finish();
+}
+
+// the pool of free cells food spawns into, one lookup per arena cell
+fn free_cells(c: &mut Criterion) {
+    let mut group = c.benchmark_group("free_cells");
+    group.sample_size(10);
+    for size in SIZES {
+        let (arena, cells) = filled(size);
+        let occupancy = occupancy(&cells);
+        group.bench_with_input(BenchmarkId::new("occupancy", size), &arena, |b, arena| {
+            b.iter(|| {
+                arena
+                    .cells()
+                    .filter(|cell| occupancy.is_empty(*cell))
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lookup, free_cells);
+criterion_main!(benches);
@@ -0,0 +1,228 @@
+use std::{fs, io};
+
+use bevy::{input::InputSystem, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+const BINDINGS_PATH: &str = "bindings.ron";
+
+pub struct ActionPlugin;
+
+impl Plugin for ActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputBindings::load_or_default())
+            .init_resource::<ActionState>()
+            .add_systems(PreUpdate, update_action_state.after(InputSystem));
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SnakeAction {
+    TurnUp,
+    TurnDown,
+    TurnLeft,
+    TurnRight,
+    PlayerTwoUp,
+    PlayerTwoDown,
+    PlayerTwoLeft,
+    PlayerTwoRight,
+    Pause,
+    Restart,
+}
+
+impl SnakeAction {
+    pub const ALL: [Self; 10] = [
+        Self::TurnUp,
+        Self::TurnDown,
+        Self::TurnLeft,
+        Self::TurnRight,
+        Self::PlayerTwoUp,
+        Self::PlayerTwoDown,
+        Self::PlayerTwoLeft,
+        Self::PlayerTwoRight,
+        Self::Pause,
+        Self::Restart,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TurnUp => "Up",
+            Self::TurnDown => "Down",
+            Self::TurnLeft => "Left",
+            Self::TurnRight => "Right",
+            Self::PlayerTwoUp => "Player 2 Up",
+            Self::PlayerTwoDown => "Player 2 Down",
+            Self::PlayerTwoLeft => "Player 2 Left",
+            Self::PlayerTwoRight => "Player 2 Right",
+            Self::Pause => "Pause",
+            Self::Restart => "Restart",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum InputBinding {
+    Key(KeyCode),
+    GamepadButton(GamepadButton),
+    // pressed when the axis is pushed past the threshold in the given direction
+    GamepadAxis { axis: GamepadAxis, positive: bool },
+}
+
+impl InputBinding {
+    const AXIS_THRESHOLD: f32 = 0.5;
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Key(key) => format!("{key:?}"),
+            Self::GamepadButton(button) => format!("{button:?}"),
+            Self::GamepadAxis { axis, positive } => {
+                format!("{axis:?}{}", if *positive { "+" } else { "-" })
+            }
+        }
+    }
+
+    fn pressed(&self, keys: &ButtonInput<KeyCode>, gamepads: &Query<&Gamepad>) -> bool {
+        match *self {
+            Self::Key(key) => keys.pressed(key),
+            Self::GamepadButton(button) => gamepads.iter().any(|gamepad| gamepad.pressed(button)),
+            Self::GamepadAxis { axis, positive } => gamepads.iter().any(|gamepad| {
+                let value = gamepad.get(axis).unwrap_or_default();
+                match positive {
+                    true => value > Self::AXIS_THRESHOLD,
+                    false => value < -Self::AXIS_THRESHOLD,
+                }
+            }),
+        }
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct InputBindings(pub HashMap<SnakeAction, Vec<InputBinding>>);
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use GamepadAxis::{LeftStickX, LeftStickY};
+        use GamepadButton::{DPadDown, DPadLeft, DPadRight, DPadUp};
+
+        Self(HashMap::from_iter([
+            (
+                SnakeAction::TurnUp,
+                Self::turn(KeyCode::KeyW, DPadUp, LeftStickY, true),
+            ),
+            (
+                SnakeAction::TurnDown,
+                Self::turn(KeyCode::KeyS, DPadDown, LeftStickY, false),
+            ),
+            (
+                SnakeAction::TurnLeft,
+                Self::turn(KeyCode::KeyA, DPadLeft, LeftStickX, false),
+            ),
+            (
+                SnakeAction::TurnRight,
+                Self::turn(KeyCode::KeyD, DPadRight, LeftStickX, true),
+            ),
+            // a lone snake also answers to player two's arrows
+            (
+                SnakeAction::PlayerTwoUp,
+                vec![InputBinding::Key(KeyCode::ArrowUp)],
+            ),
+            (
+                SnakeAction::PlayerTwoDown,
+                vec![InputBinding::Key(KeyCode::ArrowDown)],
+            ),
+            (
+                SnakeAction::PlayerTwoLeft,
+                vec![InputBinding::Key(KeyCode::ArrowLeft)],
+            ),
+            (
+                SnakeAction::PlayerTwoRight,
+                vec![InputBinding::Key(KeyCode::ArrowRight)],
+            ),
+            (
+                SnakeAction::Pause,
+                vec![
+                    InputBinding::Key(KeyCode::Escape),
+                    InputBinding::Key(KeyCode::KeyP),
+                    InputBinding::GamepadButton(GamepadButton::Start),
+                ],
+            ),
+            (
+                SnakeAction::Restart,
+                vec![
+                    InputBinding::Key(KeyCode::KeyR),
+                    InputBinding::Key(KeyCode::Enter),
+                    InputBinding::GamepadButton(GamepadButton::South),
+                ],
+            ),
+        ]))
+    }
+}
+
+impl InputBindings {
+    fn turn(
+        key: KeyCode,
+        dpad: GamepadButton,
+        axis: GamepadAxis,
+        positive: bool,
+    ) -> Vec<InputBinding> {
+        vec![
+            InputBinding::Key(key),
+            InputBinding::GamepadButton(dpad),
+            InputBinding::GamepadAxis { axis, positive },
+        ]
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        ron::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn load_or_default() -> Self {
+        match Self::load(BINDINGS_PATH) {
+            Ok(bindings) => bindings,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                warn!("failed to load {BINDINGS_PATH}, using default bindings: {e}");
+                Self::default()
+            }
+        }
+    }
+}
+
+// the abstract actions pressed this frame, from any input source
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: Vec<SnakeAction>,
+    just_pressed: Vec<SnakeAction>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: SnakeAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: SnakeAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+}
+
+fn update_action_state(
+    mut state: ResMut<ActionState>,
+    bindings: Res<InputBindings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+) {
+    let pressed: Vec<_> = bindings
+        .0
+        .iter()
+        .filter(|(_, inputs)| inputs.iter().any(|input| input.pressed(&keys, &gamepads)))
+        .map(|(action, _)| *action)
+        .collect();
+
+    // an action is only just pressed on the first frame any of its inputs are held
+    state.just_pressed = pressed
+        .iter()
+        .filter(|action| !state.pressed.contains(action))
+        .copied()
+        .collect();
+    state.pressed = pressed;
+}
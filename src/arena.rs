@@ -1,8 +1,11 @@
-use bevy::{color::palettes::tailwind, prelude::*};
+use bevy::{color::palettes::tailwind, prelude::*, utils::HashSet};
 
 use crate::{
-    game::{GameEntity, SpawnLevel, UnitCubeMesh},
+    campaign::Campaign,
+    game::{GameEntity, GameMode, SpawnLevel, UnitCubeMesh},
+    grid::GridPosition,
     level::Score,
+    rules::GameRules,
 };
 
 pub struct ArenaPlugin;
@@ -10,11 +13,20 @@ pub struct ArenaPlugin;
 impl Plugin for ArenaPlugin {
     fn build(&self, app: &mut App) {
         app.add_observer(on_spawn_level)
-            .add_observer(on_add_wall)
+            .add_systems(FixedUpdate, expand_arena.in_set(ArenaSet));
+    }
+}
+
+pub struct ArenaVisualPlugin;
+
+impl Plugin for ArenaVisualPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(on_add_wall)
+            .add_observer(on_add_obstacle)
             .add_systems(PreStartup, insert_wall_material)
             .add_systems(
                 FixedUpdate,
-                (expand_arena, resize_walls).chain().in_set(ArenaSet),
+                resize_walls.after(expand_arena).in_set(ArenaSet),
             );
     }
 }
@@ -25,55 +37,144 @@ pub struct ArenaSet;
 #[derive(Resource)]
 struct WallMaterial(Handle<StandardMaterial>);
 
+#[derive(Resource)]
+struct PortalMaterial(Handle<StandardMaterial>);
+
 #[derive(Component)]
 #[require(GameEntity, Mesh3d, MeshMaterial3d<StandardMaterial>)]
 struct Wall {
-    direction: Dir3,
-}
-
-impl Wall {
-    fn new(direction: Dir3) -> Self {
-        Self { direction }
-    }
+    // portals mark an edge the snake wraps through rather than collides with
+    portal: bool,
 }
 
+// a blocked cell inside the arena
 #[derive(Component)]
-#[require(GameEntity)]
-pub struct ArenaSize(pub i32);
+#[require(GameEntity, GridPosition)]
+pub struct Obstacle;
 
-impl Default for ArenaSize {
-    fn default() -> Self {
-        Self(11)
-    }
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+#[require(GameEntity)]
+pub struct ArenaSize {
+    pub width: i32,
+    pub height: i32,
+    // the cells of the bounding box that belong to the arena, all of them when there is no mask
+    pub mask: Option<HashSet<IVec3>>,
 }
 
 impl ArenaSize {
-    pub fn half_size(&self) -> i32 {
-        self.0 / 2
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            mask: None,
+        }
+    }
+
+    // the corner of the bounding box with the lowest coordinates
+    pub fn min(&self) -> IVec3 {
+        IVec3::new(-(self.width / 2), 0, -(self.height / 2))
+    }
+
+    // the corner of the bounding box with the highest coordinates
+    pub fn max(&self) -> IVec3 {
+        self.min() + IVec3::new(self.width - 1, 0, self.height - 1)
     }
 
     pub fn area(&self) -> i32 {
-        self.0 * self.0
+        match &self.mask {
+            Some(mask) => mask.len() as i32,
+            None => self.width * self.height,
+        }
+    }
+
+    pub fn contains(&self, position: IVec3) -> bool {
+        let (min, max) = (self.min(), self.max());
+        let in_bounds =
+            (min.x..=max.x).contains(&position.x) && (min.z..=max.z).contains(&position.z);
+        in_bounds
+            && self
+                .mask
+                .as_ref()
+                .is_none_or(|mask| mask.contains(&position))
+    }
+
+    // every cell that belongs to the arena
+    pub fn cells(&self) -> impl Iterator<Item = IVec3> + '_ {
+        let (min, max) = (self.min(), self.max());
+        (min.x..=max.x)
+            .flat_map(move |x| (min.z..=max.z).map(move |z| IVec3::new(x, 0, z)))
+            .filter(|position| self.contains(*position))
+    }
+
+    // brings a position that left the bounding box back in through the opposite edge
+    pub fn wrap(&self, position: IVec3) -> IVec3 {
+        let min = self.min();
+        IVec3::new(
+            (position.x - min.x).rem_euclid(self.width) + min.x,
+            position.y,
+            (position.z - min.z).rem_euclid(self.height) + min.z,
+        )
+    }
+
+    pub fn grow(&mut self, amount: i32) {
+        self.width += amount;
+        self.height += amount;
+    }
+
+    // the cells just outside the arena, including diagonals so corners are closed
+    fn boundary(&self) -> HashSet<IVec3> {
+        self.cells()
+            .flat_map(|position| {
+                (-1..=1).flat_map(move |x| (-1..=1).map(move |z| position + IVec3::new(x, 0, z)))
+            })
+            .filter(|position| !self.contains(*position))
+            .collect()
     }
 }
 
-fn on_spawn_level(_: Trigger<SpawnLevel>, mut commands: Commands) {
-    commands.spawn(ArenaSize::default());
-    commands.spawn(Wall::new(Dir3::NEG_X));
-    commands.spawn(Wall::new(Dir3::X));
-    commands.spawn(Wall::new(Dir3::NEG_Z));
-    commands.spawn(Wall::new(Dir3::Z));
+fn on_spawn_level(
+    _: Trigger<SpawnLevel>,
+    rules: Res<GameRules>,
+    mode: Res<GameMode>,
+    campaign: Res<Campaign>,
+    mut commands: Commands,
+) {
+    let Some(layout) = campaign.layout(*mode) else {
+        commands.spawn(ArenaSize::new(rules.arena_width, rules.arena_height));
+        return;
+    };
+
+    commands.spawn(layout.arena_size());
+    for position in layout.obstacles() {
+        commands.spawn((Obstacle, GridPosition(position)));
+    }
 }
 
 fn on_add_wall(
     trigger: Trigger<OnAdd, Wall>,
     wall_material: Res<WallMaterial>,
+    portal_material: Res<PortalMaterial>,
     unit_cube_mesh: Res<UnitCubeMesh>,
-    mut query: Query<(&mut Mesh3d, &mut MeshMaterial3d<StandardMaterial>)>,
+    mut query: Query<(&Wall, &mut Mesh3d, &mut MeshMaterial3d<StandardMaterial>)>,
 ) {
-    let (mut mesh, mut material) = query.get_mut(trigger.entity()).unwrap();
+    let (wall, mut mesh, mut material) = query.get_mut(trigger.entity()).unwrap();
     mesh.0 = unit_cube_mesh.0.clone();
-    material.0 = wall_material.0.clone();
+    material.0 = match wall.portal {
+        true => portal_material.0.clone(),
+        false => wall_material.0.clone(),
+    };
+}
+
+fn on_add_obstacle(
+    trigger: Trigger<OnAdd, Obstacle>,
+    wall_material: Res<WallMaterial>,
+    unit_cube_mesh: Res<UnitCubeMesh>,
+    mut commands: Commands,
+) {
+    commands.entity(trigger.entity()).insert((
+        Mesh3d(unit_cube_mesh.0.clone()),
+        MeshMaterial3d(wall_material.0.clone()),
+    ));
 }
 
 fn insert_wall_material(mut materials: ResMut<Assets<StandardMaterial>>, mut commands: Commands) {
@@ -82,39 +183,167 @@ fn insert_wall_material(mut materials: ResMut<Assets<StandardMaterial>>, mut com
         perceptual_roughness: 1.0,
         ..default()
     })));
+    commands.insert_resource(PortalMaterial(materials.add(StandardMaterial {
+        base_color: Color::from(tailwind::CYAN_400).with_alpha(0.6),
+        emissive: LinearRgba::from(tailwind::CYAN_400) * 2.0,
+        alpha_mode: AlphaMode::Blend,
+        perceptual_roughness: 1.0,
+        ..default()
+    })));
 }
 
 fn resize_walls(
     arena_query: Query<&ArenaSize, Changed<ArenaSize>>,
-    mut wall_query: Query<(&Wall, &mut Transform)>,
+    mut wall_query: Query<(Entity, &Wall, &mut Transform)>,
+    mode: Res<GameMode>,
+    mut commands: Commands,
 ) {
     for arena_size in arena_query.iter() {
-        for (wall, mut transform) in wall_query.iter_mut() {
-            transform.translation =
-                wall.direction.as_vec3() * (arena_size.half_size() as f32 + 1.0);
-
-            let scale_dir = if wall.direction.x != 0.0 {
-                Vec3::new(0.0, 0.0, 1.0)
-            } else {
-                Vec3::new(1.0, 0.0, 0.0)
-            };
-
-            transform.scale =
-                (Vec3::ONE - scale_dir) + scale_dir * arena_size.0 as f32 + (scale_dir * 2.0);
+        let portal = mode.wraps();
+        let mut transforms = wall_transforms(arena_size, portal).into_iter();
+
+        // existing walls of the right kind are moved into place, only the difference is spawned or despawned
+        for (entity, wall, mut transform) in wall_query.iter_mut() {
+            if wall.portal != portal {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+            match transforms.next() {
+                Some(next) => *transform = next,
+                None => commands.entity(entity).despawn_recursive(),
+            }
+        }
+        for transform in transforms {
+            commands.spawn((Wall { portal }, transform));
+        }
+    }
+}
+
+// a wall stretched along each side of a rectangular arena, or a tile on every cell bordering a
+// masked one
+fn wall_transforms(arena_size: &ArenaSize, portal: bool) -> Vec<Transform> {
+    let mut transforms: Vec<_> = match arena_size.mask {
+        Some(_) => arena_size
+            .boundary()
+            .into_iter()
+            .map(|position| Transform::from_translation(position.as_vec3()))
+            .collect(),
+        None => {
+            let (min, max) = (arena_size.min().as_vec3(), arena_size.max().as_vec3());
+            let centre = (min + max) / 2.;
+            // the top and bottom walls also cover the corners
+            let across = Vec3::new(arena_size.width as f32 + 2., 1., 1.);
+            let down = Vec3::new(1., 1., arena_size.height as f32);
+            [
+                (Vec3::new(centre.x, 0., min.z - 1.), across),
+                (Vec3::new(centre.x, 0., max.z + 1.), across),
+                (Vec3::new(min.x - 1., 0., centre.z), down),
+                (Vec3::new(max.x + 1., 0., centre.z), down),
+            ]
+            .map(|(translation, scale)| Transform::from_translation(translation).with_scale(scale))
+            .into()
+        }
+    };
+
+    // portals are a flat strip the snake can be seen passing over
+    if portal {
+        for transform in transforms.iter_mut() {
+            transform.scale.y = 0.1;
+            transform.translation.y = -0.45;
         }
     }
+    transforms
 }
 
 fn expand_arena(
     score_query: Query<&Score, Changed<Score>>,
     mut arena_query: Query<&mut ArenaSize>,
+    rules: Res<GameRules>,
+    mode: Res<GameMode>,
+    campaign: Res<Campaign>,
 ) {
+    // handcrafted layouts keep the size they were drawn at
+    if campaign.layout(*mode).is_some() {
+        return;
+    }
+
     for score in score_query.iter() {
         for mut arena_size in arena_query.iter_mut() {
-            if score.0 >= arena_size.area() as u32 / 4 {
-                //if score.0 >= 1 { // for testing
-                arena_size.0 += 2;
+            if score.0 >= arena_size.area() as u32 / rules.expansion_divisor {
+                arena_size.grow(rules.arena_growth);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn walls(world: &mut World) -> Vec<(Entity, Transform)> {
+        world
+            .query_filtered::<(Entity, &Transform), With<Wall>>()
+            .iter(world)
+            .map(|(entity, transform)| (entity, *transform))
+            .collect()
+    }
+
+    #[test]
+    fn expanding_moves_the_same_walls() {
+        let mut world = World::new();
+        world.init_resource::<GameMode>();
+        let arena = world.spawn(ArenaSize::new(11, 11)).id();
+        world.run_system_once(resize_walls).unwrap();
+        let before = walls(&mut world);
+        assert_eq!(before.len(), 4);
+
+        world.get_mut::<ArenaSize>(arena).unwrap().grow(2);
+        world.run_system_once(resize_walls).unwrap();
+        let after = walls(&mut world);
+        assert_eq!(
+            after.iter().map(|(entity, _)| *entity).collect::<Vec<_>>(),
+            before.iter().map(|(entity, _)| *entity).collect::<Vec<_>>()
+        );
+
+        // the walls now sit just outside the larger arena
+        let size = world.get::<ArenaSize>(arena).unwrap().clone();
+        let top = after
+            .iter()
+            .map(|(_, transform)| transform)
+            .find(|transform| transform.translation.z == (size.min().z - 1) as f32)
+            .unwrap();
+        assert_eq!(top.scale.x, (size.width + 2) as f32);
+    }
+
+    #[test]
+    fn portal_walls_are_replaced_by_solid_ones() {
+        let mut world = World::new();
+        world.init_resource::<GameMode>();
+        world.spawn(ArenaSize::new(11, 11));
+        world.spawn((Wall { portal: true }, Transform::default()));
+        world.run_system_once(resize_walls).unwrap();
+
+        let portals = world
+            .query::<&Wall>()
+            .iter(&world)
+            .filter(|wall| wall.portal)
+            .count();
+        assert_eq!(portals, 0);
+        assert_eq!(walls(&mut world).len(), 4);
+    }
+
+    #[test]
+    fn masked_arenas_get_a_wall_on_every_bordering_cell() {
+        let mut world = World::new();
+        world.init_resource::<GameMode>();
+        let mut arena = ArenaSize::new(3, 3);
+        arena.mask = Some(arena.cells().filter(|cell| *cell != IVec3::ZERO).collect());
+        let boundary = arena.boundary().len();
+        world.spawn(arena);
+        world.run_system_once(resize_walls).unwrap();
+
+        assert_eq!(walls(&mut world).len(), boundary);
+    }
+}
@@ -0,0 +1,18 @@
+use std::io::{self, BufRead, Write};
+
+use snake::environment::Environment;
+
+// a training environment speaking one json command and reply per line, see `environment::Command`
+fn main() -> io::Result<()> {
+    let mut environment = Environment::new();
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(stdout, "{}", environment.respond(&line))?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
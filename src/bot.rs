@@ -0,0 +1,370 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{action::ActionState, arena::ArenaSize, occupancy::Occupancy};
+
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Opponent>();
+    }
+}
+
+// decides where a snake goes next, whether the keyboard, a replay or a bot is steering it
+//
+// controllers are asked every fixed update before the snakes move, bots only once their last turn
+// has been applied, and players until they have no more turns to make on that tick
+pub trait SnakeController: Send + Sync {
+    // returning `None` or the current direction keeps the snake going straight
+    fn next_direction(&mut self, view: &GridView) -> Option<Dir3>;
+
+    // a player's turns move the snake straight away and are recorded in replays, a bot's wait for
+    // its next move and make the same moves again on playback
+    fn is_player(&self) -> bool {
+        false
+    }
+}
+
+// steers a snake head, heads without one only turn when `TurnSnake` is triggered on them directly
+#[derive(Component)]
+pub struct SnakeControl(pub Box<dyn SnakeController>);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum BotKind {
+    // heads for the nearest food without looking further than the next cell
+    Greedy,
+    // takes the shortest path to food around every snake and obstacle
+    Pathfinding,
+    // follows a cycle through every cell, so it never traps itself
+    Hamiltonian,
+}
+
+impl BotKind {
+    pub const ALL: [Self; 3] = [Self::Greedy, Self::Pathfinding, Self::Hamiltonian];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Greedy => "Greedy",
+            Self::Pathfinding => "Pathfinding",
+            Self::Hamiltonian => "Perfect",
+        }
+    }
+
+    pub fn controller(&self) -> Box<dyn SnakeController> {
+        match self {
+            Self::Greedy => Box::new(GreedyBot),
+            Self::Pathfinding => Box::new(PathfindingBot),
+            Self::Hamiltonian => Box::new(HamiltonianBot::default()),
+        }
+    }
+}
+
+// the bot controlling player two in versus, a second human when empty
+#[derive(Resource, Default, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Opponent(pub Option<BotKind>);
+
+impl Opponent {
+    pub fn name(&self) -> &'static str {
+        self.0.as_ref().map_or("Human", BotKind::name)
+    }
+
+    pub fn next(&self) -> Self {
+        let index = self
+            .0
+            .and_then(|kind| BotKind::ALL.iter().position(|k| *k == kind));
+        Self(match index {
+            Some(index) => BotKind::ALL.get(index + 1).copied(),
+            None => Some(BotKind::ALL[0]),
+        })
+    }
+}
+
+// what a controller can see of the level when choosing its next move
+pub struct GridView<'a> {
+    pub tick: u64,
+    // the keys held down, missing when running headless
+    pub actions: Option<&'a ActionState>,
+    pub head: IVec3,
+    pub direction: Dir3,
+    pub arena: &'a ArenaSize,
+    pub wraps: bool,
+    pub occupancy: &'a Occupancy,
+    pub food: &'a [IVec3],
+}
+
+impl GridView<'_> {
+    const DIRECTIONS: [Dir3; 4] = [Dir3::NEG_Z, Dir3::X, Dir3::Z, Dir3::NEG_X];
+
+    // the cell one step from the given one, which may lie outside the arena
+    pub fn step(&self, position: IVec3, direction: Dir3) -> IVec3 {
+        let next = position + direction.as_ivec3();
+        match self.wraps {
+            true => self.arena.wrap(next),
+            false => next,
+        }
+    }
+
+    // the direction leading from one cell to a neighbouring one
+    pub fn direction_to(&self, from: IVec3, to: IVec3) -> Option<Dir3> {
+        Self::DIRECTIONS
+            .into_iter()
+            .find(|direction| self.step(from, *direction) == to)
+    }
+
+    pub fn is_free(&self, position: IVec3) -> bool {
+        self.arena.contains(position) && !self.occupancy.is_solid(position)
+    }
+
+    // the moves that don't crash on the next step, going straight first
+    pub fn safe_moves(&self) -> impl Iterator<Item = (Dir3, IVec3)> + '_ {
+        let straight = std::iter::once(self.direction);
+        let turns = Self::DIRECTIONS
+            .into_iter()
+            .filter(|d| *d != self.direction && *d != -self.direction);
+        straight
+            .chain(turns)
+            .map(|direction| (direction, self.step(self.head, direction)))
+            .filter(|(_, position)| self.is_free(*position))
+    }
+
+    // the number of free cells reachable from the given one
+    fn reachable(&self, from: IVec3) -> usize {
+        let mut visited = HashSet::from_iter([from]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(position) = queue.pop_front() {
+            for direction in Self::DIRECTIONS {
+                let next = self.step(position, direction);
+                if self.is_free(next) && visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        visited.len()
+    }
+
+    // the first move of a shortest path to any food
+    fn path_to_food(&self) -> Option<Dir3> {
+        let food: HashSet<_> = self.food.iter().copied().collect();
+        let mut visited = HashSet::from_iter([self.head]);
+        let mut queue: VecDeque<_> = self
+            .safe_moves()
+            .filter(|(_, position)| visited.insert(*position))
+            .collect();
+        while let Some((first, position)) = queue.pop_front() {
+            if food.contains(&position) {
+                return Some(first);
+            }
+            for direction in Self::DIRECTIONS {
+                let next = self.step(position, direction);
+                if self.is_free(next) && visited.insert(next) {
+                    queue.push_back((first, next));
+                }
+            }
+        }
+        None
+    }
+
+    // the move leaving the most room, for when there is nothing better to do
+    fn roomiest_move(&self) -> Option<Dir3> {
+        self.safe_moves()
+            .map(|(direction, position)| (direction, self.reachable(position)))
+            .reduce(|best, next| if next.1 > best.1 { next } else { best })
+            .map(|(direction, _)| direction)
+    }
+}
+
+pub struct GreedyBot;
+
+impl SnakeController for GreedyBot {
+    fn next_direction(&mut self, view: &GridView) -> Option<Dir3> {
+        let distance = |position: IVec3| {
+            view.food
+                .iter()
+                .map(|food| (*food - position).abs().element_sum())
+                .min()
+                .unwrap_or_default()
+        };
+        view.safe_moves()
+            .min_by_key(|(_, position)| distance(*position))
+            .map(|(direction, _)| direction)
+    }
+}
+
+pub struct PathfindingBot;
+
+impl SnakeController for PathfindingBot {
+    fn next_direction(&mut self, view: &GridView) -> Option<Dir3> {
+        view.path_to_food().or_else(|| view.roomiest_move())
+    }
+}
+
+#[derive(Default)]
+pub struct HamiltonianBot {
+    // the arena the cycle was built for, and the cell following each one on it
+    cycle: Option<(ArenaSize, HashMap<IVec3, IVec3>)>,
+}
+
+impl HamiltonianBot {
+    fn cycle(&mut self, arena: &ArenaSize) -> &HashMap<IVec3, IVec3> {
+        if self.cycle.as_ref().is_none_or(|(size, _)| size != arena) {
+            let order = hamiltonian_cycle(arena);
+            let successors = order
+                .iter()
+                .zip(order.iter().cycle().skip(1))
+                .map(|(cell, next)| (*cell, *next))
+                .collect();
+            self.cycle = Some((arena.clone(), successors));
+        }
+        &self.cycle.as_ref().unwrap().1
+    }
+}
+
+impl SnakeController for HamiltonianBot {
+    fn next_direction(&mut self, view: &GridView) -> Option<Dir3> {
+        let cycle = self.cycle(view.arena);
+
+        let next = match cycle.get(&view.head) {
+            // food off the cycle next to the head is eaten by skipping the next cell and rejoining
+            // right after it
+            Some(next) => view
+                .food
+                .iter()
+                .copied()
+                .find(|food| {
+                    !cycle.contains_key(food)
+                        && view.is_free(*food)
+                        && view.direction_to(view.head, *food).is_some()
+                        && view.direction_to(*food, cycle[next]).is_some()
+                })
+                .unwrap_or(*next),
+            // having left the cycle, rejoin it wherever it is closest, arenas without a cycle never
+            // have one to rejoin
+            None => match view
+                .safe_moves()
+                .map(|(_, position)| position)
+                .find(|position| cycle.contains_key(position))
+            {
+                Some(position) => position,
+                None => return PathfindingBot.next_direction(view),
+            },
+        };
+
+        // cells the cycle can't get through are left to the pathfinding bot
+        view.direction_to(view.head, next)
+            .filter(|direction| view.is_free(next) && *direction != -view.direction)
+            .or_else(|| PathfindingBot.next_direction(view))
+    }
+}
+
+// visits every cell of a rectangular arena once and returns to the start, leaving out the bottom
+// right corner when both sides are odd
+fn hamiltonian_cycle(arena: &ArenaSize) -> Vec<IVec3> {
+    let (width, height) = (arena.width, arena.height);
+    if arena.mask.is_some() || width < 2 || height < 2 {
+        return Vec::new();
+    }
+
+    let mut cycle = match (width % 2, height % 2) {
+        (_, 0) => serpentine(width, height),
+        (0, _) => serpentine(height, width)
+            .into_iter()
+            .map(|cell| IVec3::new(cell.z, 0, cell.x))
+            .collect(),
+        _ => {
+            // cover all but the last column, then detour through it a pair of cells at a time
+            let mut cycle: Vec<_> = serpentine(height, width - 1)
+                .into_iter()
+                .map(|cell| IVec3::new(cell.z, 0, cell.x))
+                .collect();
+            for z in (0..height - 1).step_by(2) {
+                let pair = [IVec3::new(width - 2, 0, z), IVec3::new(width - 2, 0, z + 1)];
+                let Some(index) = (0..cycle.len()).find(|i| {
+                    let edge = [cycle[*i], cycle[(i + 1) % cycle.len()]];
+                    edge == pair || edge == [pair[1], pair[0]]
+                }) else {
+                    return Vec::new();
+                };
+                let detour =
+                    [cycle[index], cycle[(index + 1) % cycle.len()]].map(|cell| cell + IVec3::X);
+                cycle.splice(index + 1..index + 1, detour);
+            }
+            cycle
+        }
+    };
+
+    let min = arena.min();
+    for cell in cycle.iter_mut() {
+        *cell += min;
+    }
+    cycle
+}
+
+// a cycle over a grid with an even height, sweeping each row right of the first column and coming
+// back up the first column
+fn serpentine(width: i32, height: i32) -> Vec<IVec3> {
+    let rows = (0..height).flat_map(|z| {
+        let xs: Vec<_> = match z % 2 {
+            0 => (1..width).collect(),
+            _ => (1..width).rev().collect(),
+        };
+        xs.into_iter().map(move |x| IVec3::new(x, 0, z))
+    });
+    let column = (0..height).rev().map(|z| IVec3::new(0, 0, z));
+    rows.chain(column).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a 5x5 arena missing its top row, with the snake heading straight for the gap
+    fn masked_view<'a>(
+        arena: &'a ArenaSize,
+        occupancy: &'a Occupancy,
+        food: &'a [IVec3],
+    ) -> GridView<'a> {
+        GridView {
+            tick: 0,
+            actions: None,
+            head: IVec3::new(0, 0, -1),
+            direction: Dir3::NEG_Z,
+            arena,
+            wraps: false,
+            occupancy,
+            food,
+        }
+    }
+
+    #[test]
+    fn hamiltonian_bot_avoids_walls_on_masked_arenas() {
+        let mut arena = ArenaSize::new(5, 5);
+        arena.mask = Some(arena.cells().filter(|cell| cell.z > -2).collect());
+        assert!(hamiltonian_cycle(&arena).is_empty());
+
+        let occupancy = Occupancy::default();
+        let food = [IVec3::new(2, 0, 1)];
+        let view = masked_view(&arena, &occupancy, &food);
+
+        let direction = HamiltonianBot::default().next_direction(&view);
+        assert_eq!(direction, Some(Dir3::X));
+        assert!(view.is_free(view.step(view.head, direction.unwrap())));
+    }
+
+    #[test]
+    fn hamiltonian_bot_survives_without_food_on_masked_arenas() {
+        let mut arena = ArenaSize::new(5, 5);
+        arena.mask = Some(arena.cells().filter(|cell| cell.z > -2).collect());
+
+        let occupancy = Occupancy::default();
+        let view = masked_view(&arena, &occupancy, &[]);
+
+        let direction = HamiltonianBot::default().next_direction(&view);
+        assert!(direction.is_some_and(|d| view.is_free(view.step(view.head, d))));
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arena::ArenaSize,
+    food::FoodSet,
+    game::{DespawnGameEntities, GameMode, GameState, SpawnLevel},
+    level::Score,
+};
+
+const LEVELS_DIRECTORY: &str = "assets/levels";
+
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().contains_resource::<Campaign>() {
+            app.insert_resource(Campaign::load(LEVELS_DIRECTORY));
+        }
+
+        app.add_systems(
+            FixedUpdate,
+            complete_level
+                .run_if(in_state(GameState::Play))
+                .after(FoodSet),
+        );
+    }
+}
+
+// a handcrafted arena, drawn row by row with '#' for obstacles, '.' for open floor and ' ' for
+// cells outside the arena
+#[derive(Deserialize, Clone, Debug)]
+pub struct LevelLayout {
+    pub name: String,
+    // reaching this score completes the level and unlocks the next one
+    pub target_score: u32,
+    pub map: Vec<String>,
+}
+
+impl LevelLayout {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let layout: Self =
+            ron::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        layout
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(layout)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let width = self.width();
+        if width == 0 {
+            return Err("map must have at least one cell".into());
+        }
+        if let Some(row) = self
+            .map
+            .iter()
+            .find(|row| row.chars().count() as i32 != width)
+        {
+            return Err(format!(
+                "every row must be {width} cells wide, found \"{row}\""
+            ));
+        }
+        if let Some(cell) = self
+            .map
+            .concat()
+            .chars()
+            .find(|c| !matches!(c, '#' | '.' | ' '))
+        {
+            return Err(format!("unknown cell '{cell}'"));
+        }
+
+        // the snake always starts in the centre
+        if self
+            .cells()
+            .all(|(position, cell)| position != IVec3::ZERO || cell != '.')
+        {
+            return Err("the centre cell must be open floor".into());
+        }
+        Ok(())
+    }
+
+    fn width(&self) -> i32 {
+        self.map.first().map_or(0, |row| row.chars().count() as i32)
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (IVec3, char)> + '_ {
+        let min = ArenaSize::new(self.width(), self.map.len() as i32).min();
+        self.map.iter().enumerate().flat_map(move |(z, row)| {
+            row.chars()
+                .enumerate()
+                .map(move |(x, cell)| (min + IVec3::new(x as i32, 0, z as i32), cell))
+        })
+    }
+
+    pub fn arena_size(&self) -> ArenaSize {
+        let mut arena_size = ArenaSize::new(self.width(), self.map.len() as i32);
+
+        // only irregular shapes need a mask
+        if self.map.iter().any(|row| row.contains(' ')) {
+            arena_size.mask = Some(
+                self.cells()
+                    .filter_map(|(position, cell)| (cell != ' ').then_some(position))
+                    .collect(),
+            );
+        }
+        arena_size
+    }
+
+    pub fn obstacles(&self) -> impl Iterator<Item = IVec3> + '_ {
+        self.cells()
+            .filter_map(|(position, cell)| (cell == '#').then_some(position))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Campaign {
+    pub levels: Vec<LevelLayout>,
+    // the level played next in campaign mode
+    pub current: usize,
+    // the furthest level the player has reached
+    pub unlocked: usize,
+}
+
+impl Campaign {
+    fn load(directory: impl AsRef<Path>) -> Self {
+        let mut paths: Vec<_> = match fs::read_dir(directory) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+                .collect(),
+            Err(e) => {
+                warn!("failed to read campaign levels: {e}");
+                Vec::new()
+            }
+        };
+
+        // levels are played in file name order
+        paths.sort();
+        let levels: Vec<_> = paths
+            .iter()
+            .filter_map(|path| match LevelLayout::load(path) {
+                Ok(layout) => Some(layout),
+                Err(e) => {
+                    warn!("skipping level {}: {e}", path.display());
+                    None
+                }
+            })
+            .collect();
+
+        let unlocked = CampaignProgress::load()
+            .unlocked
+            .min(levels.len().saturating_sub(1));
+        Self {
+            levels,
+            current: unlocked,
+            unlocked,
+        }
+    }
+
+    // the layout to build the arena from, if the mode uses one
+    pub fn layout(&self, mode: GameMode) -> Option<&LevelLayout> {
+        match mode {
+            GameMode::Campaign => self.levels.get(self.current),
+            _ => None,
+        }
+    }
+
+    // cycles the current level through the unlocked ones
+    pub fn select_next(&mut self) {
+        self.current = match self.current < self.unlocked {
+            true => self.current + 1,
+            false => 0,
+        };
+    }
+
+    fn unlock(&mut self, index: usize) {
+        if index <= self.unlocked {
+            return;
+        }
+
+        self.unlocked = index;
+        let progress = CampaignProgress {
+            unlocked: self.unlocked,
+        };
+        if let Err(e) = progress.save() {
+            error!("failed to save campaign progress: {e}");
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct CampaignProgress {
+    unlocked: usize,
+}
+
+impl CampaignProgress {
+    fn path() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_default()
+            .join("snake")
+            .join("campaign.ron")
+    }
+
+    fn load() -> Self {
+        let contents = match fs::read_to_string(Self::path()) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                warn!("failed to read campaign progress: {e}");
+                return Self::default();
+            }
+        };
+
+        ron::from_str(&contents).unwrap_or_else(|e| {
+            warn!("failed to parse campaign progress: {e}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let contents =
+            ron::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let temp_path = path.with_extension("ron.tmp");
+        fs::write(&temp_path, contents)?;
+        fs::rename(temp_path, path)
+    }
+}
+
+fn complete_level(
+    score_query: Query<&Score, Changed<Score>>,
+    mode: Res<GameMode>,
+    mut campaign: ResMut<Campaign>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    let Some(target_score) = campaign.layout(*mode).map(|layout| layout.target_score) else {
+        return;
+    };
+    if score_query.iter().all(|score| score.0 < target_score) {
+        return;
+    }
+
+    // finishing the last level ends the campaign
+    let next = campaign.current + 1;
+    if next >= campaign.levels.len() {
+        next_state.set(GameState::Menu);
+        return;
+    }
+
+    campaign.unlock(next);
+    campaign.current = next;
+    commands.trigger(DespawnGameEntities);
+    commands.trigger(SpawnLevel);
+}
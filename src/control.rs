@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+
+use crate::{
+    action::{ActionState, SnakeAction},
+    arena::ArenaSize,
+    bot::{BotKind, GridView, Opponent, SnakeControl, SnakeController},
+    food::Food,
+    game::{GameMode, GameState, PlayState},
+    grid::GridPosition,
+    level::LevelTick,
+    occupancy::Occupancy,
+    replay::{ReplayController, ReplayMode},
+    snake::{Player, SnakeDirection, SnakeHead, SnakeSet, SnakeTurnQueue, TurnSnake},
+};
+
+// the bot playing the demo behind the title screen
+const DEMO_BOT: BotKind = BotKind::Hamiltonian;
+
+pub struct ControlPlugin;
+
+impl Plugin for ControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(on_add_snake_head).add_systems(
+            FixedUpdate,
+            control_snakes
+                .run_if(not(in_state(PlayState::Over)))
+                .before(SnakeSet),
+        );
+    }
+}
+
+// turns a snake with the arrow keys, or player two's keys in versus
+pub struct KeyboardController {
+    // in order of priority when several are pressed at once
+    turn_actions: Vec<(SnakeAction, Dir3)>,
+    // the actions held down last time, so a key held across several ticks only turns once
+    held: Vec<SnakeAction>,
+}
+
+impl KeyboardController {
+    pub fn new(players: &[Player]) -> Self {
+        Self {
+            turn_actions: players.iter().flat_map(Player::turn_actions).collect(),
+            held: Vec::new(),
+        }
+    }
+}
+
+impl SnakeController for KeyboardController {
+    fn next_direction(&mut self, view: &GridView) -> Option<Dir3> {
+        let actions = view.actions?;
+        let held: Vec<_> = self
+            .turn_actions
+            .iter()
+            .map(|(action, _)| *action)
+            .filter(|action| actions.pressed(*action))
+            .collect();
+        let direction = self
+            .turn_actions
+            .iter()
+            .find(|(action, _)| held.contains(action) && !self.held.contains(action))
+            .map(|(_, direction)| *direction);
+        self.held = held;
+        direction
+    }
+
+    fn is_player(&self) -> bool {
+        true
+    }
+}
+
+// the demo behind the title screen is played by bots, versus can put one in player two's seat, and
+// everyone else plays from the keyboard or a replay, training agents turn their snake themselves
+fn on_add_snake_head(
+    trigger: Trigger<OnAdd, SnakeHead>,
+    query: Query<&Player>,
+    state: Res<State<GameState>>,
+    opponent: Res<Opponent>,
+    replay_mode: Res<ReplayMode>,
+    mode: Res<GameMode>,
+    mut commands: Commands,
+) {
+    let player = query.get(trigger.entity()).copied().unwrap_or_default();
+    let controller: Box<dyn SnakeController> = match (state.get(), player, opponent.0) {
+        (GameState::Menu, ..) => DEMO_BOT.controller(),
+        (_, Player::Two, Some(kind)) => kind.controller(),
+        _ => match &*replay_mode {
+            ReplayMode::Play(replay) => Box::new(ReplayController::new(replay, player)),
+            // a lone player answers to both sets of controls
+            ReplayMode::Record if mode.players() == 1 || opponent.0.is_some() => {
+                Box::new(KeyboardController::new(&Player::ALL))
+            }
+            ReplayMode::Record => Box::new(KeyboardController::new(&[player])),
+            ReplayMode::Off => return,
+        },
+    };
+    commands
+        .entity(trigger.entity())
+        .insert(SnakeControl(controller));
+}
+
+fn control_snakes(
+    mut head_query: Query<(
+        Entity,
+        &mut SnakeControl,
+        &GridPosition,
+        &SnakeDirection,
+        &mut SnakeTurnQueue,
+    )>,
+    food_query: Query<&GridPosition, With<Food>>,
+    arena_query: Query<&ArenaSize>,
+    tick_query: Query<&LevelTick>,
+    occupancy: Res<Occupancy>,
+    actions: Option<Res<ActionState>>,
+    mode: Res<GameMode>,
+    mut commands: Commands,
+) {
+    let (Some(arena), Ok(tick)) = (arena_query.iter().next(), tick_query.get_single()) else {
+        return;
+    };
+
+    let food: Vec<_> = food_query.iter().map(|gp| gp.0).collect();
+
+    for (entity, mut control, grid_position, direction, mut queue) in head_query.iter_mut() {
+        let player = control.0.is_player();
+
+        // bots decide once the previous decision has been applied
+        if !player && !queue.is_empty() {
+            continue;
+        }
+
+        let view = GridView {
+            tick: tick.0,
+            actions: actions.as_deref(),
+            head: grid_position.0,
+            direction: direction.0,
+            arena,
+            wraps: mode.wraps(),
+            occupancy: &occupancy,
+            food: &food,
+        };
+
+        // player turns go through `TurnSnake`, which moves the snake straight away and records
+        // the turn, players can also make several turns on the same tick
+        if player {
+            while let Some(next_direction) = control.0.next_direction(&view) {
+                commands.trigger_targets(TurnSnake(next_direction), entity);
+            }
+        } else if let Some(next_direction) = control.0.next_direction(&view) {
+            queue.push(direction.0, next_direction);
+        }
+    }
+}
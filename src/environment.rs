@@ -0,0 +1,319 @@
+use bevy::{
+    app::PluginsState, prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy,
+    utils::HashSet,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arena::{ArenaSize, Obstacle},
+    food::Food,
+    game::{DespawnGameEntities, GameMode, GameState, SpawnLevel},
+    grid::GridPosition,
+    level::Score,
+    power_up::PowerUp,
+    random::SeedSource,
+    replay::ReplayMode,
+    snake::{SnakeBody, SnakeCrashed, SnakeDirection, SnakeHead, TurnSnake},
+    SnakeRulesPlugins,
+};
+
+// gives up on a move that takes longer than this many fixed updates
+const MAX_UPDATES_PER_STEP: u32 = 100_000;
+
+// runs the rule plugins for a training agent, must be added before them
+pub struct EnvironmentPlugin;
+
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<StatesPlugin>() {
+            app.add_plugins(StatesPlugin);
+        }
+
+        // agents choose every turn themselves and their games aren't saved
+        app.insert_resource(ReplayMode::Off)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(
+                Time::<Fixed>::default().timestep(),
+            ))
+            .add_systems(OnEnter(GameState::Load), skip_loading);
+    }
+}
+
+// a request on one line of the protocol
+#[derive(Deserialize, Debug)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Command {
+    // starts a new game, only single snake modes without levels are supported
+    Reset {
+        seed: u64,
+        #[serde(default)]
+        mode: GameMode,
+    },
+    // turns the snake, or keeps it going straight without an action, then advances one move
+    Step {
+        #[serde(default)]
+        action: Option<Heading>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Heading {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Heading {
+    pub fn direction(&self) -> Dir3 {
+        match self {
+            Self::Up => Dir3::NEG_Z,
+            Self::Down => Dir3::Z,
+            Self::Left => Dir3::NEG_X,
+            Self::Right => Dir3::X,
+        }
+    }
+
+    fn from_direction(direction: Dir3) -> Self {
+        match direction {
+            Dir3::Z => Self::Down,
+            Dir3::NEG_X => Self::Left,
+            Dir3::X => Self::Right,
+            _ => Self::Up,
+        }
+    }
+}
+
+// the reply to a command on one line of the protocol
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum Reply {
+    Step(Step),
+    Error { error: String },
+}
+
+#[derive(Serialize, Debug)]
+pub struct Step {
+    pub observation: Observation,
+    // the score gained by the move, or -1 when the snake crashed
+    pub reward: f32,
+    pub done: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Observation {
+    // the arena's bounding box one row at a time from the top, using '.' for open floor, '#' for
+    // obstacles, ' ' for cells outside the arena, 'H' for the head, 'o' for the body, '*' for food
+    // and '+' for power-ups
+    pub cells: Vec<String>,
+    // positions are [x, z] grid coordinates, the same ones the game uses
+    pub head: [i32; 2],
+    pub direction: Heading,
+    pub food: Vec<[i32; 2]>,
+    pub arena: ArenaObservation,
+    pub score: u32,
+    pub length: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ArenaObservation {
+    pub width: i32,
+    pub height: i32,
+    // the top left cell of the bounding box, the arena grows as the score rises
+    pub min: [i32; 2],
+}
+
+// the game's own rules stepped one snake move at a time, so agents trained on it play the real game
+pub struct Environment {
+    app: App,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.insert_resource(SeedSource::Fixed(0))
+            .add_plugins(MinimalPlugins)
+            .add_plugins(EnvironmentPlugin)
+            .add_plugins(SnakeRulesPlugins);
+        while app.plugins_state() == PluginsState::Adding {
+            bevy::tasks::tick_global_task_pools_on_main_thread();
+        }
+        app.finish();
+        app.cleanup();
+
+        // get through loading into a level
+        while *app.world().resource::<State<GameState>>() != GameState::Play {
+            app.update();
+        }
+        Self { app }
+    }
+
+    // answers one line of the protocol with one line of json
+    pub fn respond(&mut self, line: &str) -> String {
+        let reply = match serde_json::from_str(line) {
+            Ok(command) => match self.run(command) {
+                Ok(step) => Reply::Step(step),
+                Err(error) => Reply::Error { error },
+            },
+            Err(e) => Reply::Error {
+                error: e.to_string(),
+            },
+        };
+        serde_json::to_string(&reply).unwrap_or_default()
+    }
+
+    pub fn run(&mut self, command: Command) -> Result<Step, String> {
+        match command {
+            Command::Reset { seed, mode } => self.reset(seed, mode),
+            Command::Step { action } => self.step(action),
+        }
+    }
+
+    pub fn reset(&mut self, seed: u64, mode: GameMode) -> Result<Step, String> {
+        if matches!(mode, GameMode::Campaign) || mode.players() > 1 {
+            return Err(format!("{} games can't be played by an agent", mode.name()));
+        }
+
+        let world = self.app.world_mut();
+        world.insert_resource(SeedSource::Fixed(seed));
+        world.insert_resource(mode);
+        world.trigger(DespawnGameEntities);
+        world.trigger(SpawnLevel);
+
+        // the first food appears on the first fixed update
+        self.app.update();
+        Ok(Step {
+            observation: self.observe()?,
+            reward: 0.0,
+            done: false,
+        })
+    }
+
+    pub fn step(&mut self, action: Option<Heading>) -> Result<Step, String> {
+        let (head, position, score, crashed) = self.head()?;
+        if crashed {
+            return Ok(Step {
+                observation: self.observe()?,
+                reward: 0.0,
+                done: true,
+            });
+        }
+
+        if let Some(heading) = action {
+            self.app
+                .world_mut()
+                .trigger_targets(TurnSnake(heading.direction()), head);
+        }
+
+        // a move is over once the head has left its cell or crashed
+        for _ in 0..MAX_UPDATES_PER_STEP {
+            self.app.update();
+            let (_, next_position, _, crashed) = self.head()?;
+            if crashed || next_position != position {
+                break;
+            }
+        }
+
+        let (_, _, next_score, crashed) = self.head()?;
+        Ok(Step {
+            observation: self.observe()?,
+            reward: match crashed {
+                true => -1.0,
+                false => next_score as f32 - score as f32,
+            },
+            done: crashed,
+        })
+    }
+
+    fn head(&mut self) -> Result<(Entity, IVec3, u32, bool), String> {
+        let world = self.app.world_mut();
+        world
+            .query_filtered::<(Entity, &GridPosition, &Score, Has<SnakeCrashed>), With<SnakeHead>>()
+            .iter(world)
+            .next()
+            .map(|(entity, grid_position, score, crashed)| {
+                (entity, grid_position.0, score.0, crashed)
+            })
+            .ok_or_else(|| "no game is running, send a reset first".to_string())
+    }
+
+    fn observe(&mut self) -> Result<Observation, String> {
+        let (head, position, score, _) = self.head()?;
+        let world = self.app.world_mut();
+        let arena = world
+            .query::<&ArenaSize>()
+            .iter(world)
+            .next()
+            .cloned()
+            .ok_or("the level has no arena")?;
+        let direction = world
+            .get::<SnakeDirection>(head)
+            .map_or(Dir3::NEG_Z, |direction| direction.0);
+        let body: Vec<_> = world
+            .get::<SnakeBody>(head)
+            .into_iter()
+            .flat_map(SnakeBody::iter)
+            .filter_map(|segment| world.get::<GridPosition>(segment))
+            .map(|grid_position| grid_position.0)
+            .collect();
+        let obstacles: HashSet<_> = world
+            .query_filtered::<&GridPosition, With<Obstacle>>()
+            .iter(world)
+            .map(|grid_position| grid_position.0)
+            .collect();
+        let food: Vec<_> = world
+            .query_filtered::<&GridPosition, With<Food>>()
+            .iter(world)
+            .map(|grid_position| grid_position.0)
+            .collect();
+        let power_ups: HashSet<_> = world
+            .query_filtered::<&GridPosition, With<PowerUp>>()
+            .iter(world)
+            .map(|grid_position| grid_position.0)
+            .collect();
+
+        let (min, max) = (arena.min(), arena.max());
+        let cells = (min.z..=max.z)
+            .map(|z| {
+                (min.x..=max.x)
+                    .map(|x| {
+                        let cell = IVec3::new(x, 0, z);
+                        match cell {
+                            _ if !arena.contains(cell) => ' ',
+                            _ if cell == position => 'H',
+                            _ if body.contains(&cell) => 'o',
+                            _ if obstacles.contains(&cell) => '#',
+                            _ if food.contains(&cell) => '*',
+                            _ if power_ups.contains(&cell) => '+',
+                            _ => '.',
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(Observation {
+            cells,
+            head: [position.x, position.z],
+            direction: Heading::from_direction(direction),
+            food: food.iter().map(|cell| [cell.x, cell.z]).collect(),
+            arena: ArenaObservation {
+                width: arena.width,
+                height: arena.height,
+                min: [min.x, min.z],
+            },
+            score,
+            length: body.len() as u32 + 1,
+        })
+    }
+}
+
+fn skip_loading(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Play);
+}
@@ -1,12 +1,17 @@
-use bevy::{color::palettes::tailwind, prelude::*};
-use rand::{thread_rng, Rng};
+use bevy::{color::palettes::tailwind, prelude::*, utils::HashMap};
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     arena::ArenaSize,
     game::GameEntity,
     grid::{GridPosition, GridSet},
-    level::Score,
-    snake::{SnakeBodyBuffer, SnakeHead, SnakeSet},
+    level::{LevelTick, Score},
+    occupancy::Occupancy,
+    power_up::{PowerUpKind, SnakeEffects},
+    random::GameRng,
+    rules::GameRules,
+    snake::{ShrinkSnake, SnakeBodyBuffer, SnakeHead, SnakeSet, SnakeSpeedEffect},
 };
 
 pub struct FoodPlugin;
@@ -14,81 +19,261 @@ pub struct FoodPlugin;
 impl Plugin for FoodPlugin {
     fn build(&self, app: &mut App) {
         app.configure_sets(FixedUpdate, FoodSet.after(SnakeSet).before(GridSet))
-            .add_observer(on_add_food)
-            .add_systems(PreStartup, (insert_food_material, insert_food_mesh))
-            .add_systems(FixedUpdate, (eat_food, spawn_food).chain().in_set(FoodSet));
+            .add_systems(
+                FixedUpdate,
+                (expire_food, attract_food, eat_food, spawn_food)
+                    .chain()
+                    .in_set(FoodSet),
+            );
+    }
+}
+
+pub struct FoodVisualPlugin;
+
+impl Plugin for FoodVisualPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(on_add_food)
+            .add_systems(PreStartup, (insert_food_materials, insert_food_meshes));
     }
 }
 
 #[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
 pub struct FoodSet;
 
+// triggered on the head of a snake that ate a food
+#[derive(Event, Clone, Copy)]
+pub struct FoodEaten(pub FoodKind);
+
 #[derive(Resource)]
-struct FoodMaterial(Handle<StandardMaterial>);
+struct FoodMaterials(HashMap<FoodKind, Handle<StandardMaterial>>);
 
 #[derive(Resource)]
-struct FoodMesh(Handle<Mesh>);
+struct FoodMeshes(HashMap<FoodKind, Handle<Mesh>>);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum FoodKind {
+    // always present, eating it spawns the next one
+    Normal,
+    Golden,
+    Shrink,
+    Speed,
+    Slow,
+    Bonus,
+}
+
+impl FoodKind {
+    pub const ALL: [Self; 6] = [
+        Self::Normal,
+        Self::Golden,
+        Self::Shrink,
+        Self::Speed,
+        Self::Slow,
+        Self::Bonus,
+    ];
 
+    pub const SPECIAL: [Self; 5] = [
+        Self::Golden,
+        Self::Shrink,
+        Self::Speed,
+        Self::Slow,
+        Self::Bonus,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Golden => "Golden",
+            Self::Shrink => "Shrink",
+            Self::Speed => "Speed",
+            Self::Slow => "Slow",
+            Self::Bonus => "Bonus",
+        }
+    }
+
+    fn mesh(&self) -> Mesh {
+        match self {
+            Self::Normal => Sphere::new(0.5).into(),
+            Self::Golden => Cylinder::new(0.4, 0.15).into(),
+            Self::Shrink => Sphere::new(0.3).into(),
+            Self::Speed => Cone::new(0.4, 0.8).into(),
+            Self::Slow => Cuboid::from_length(0.6).into(),
+            Self::Bonus => Torus::new(0.2, 0.45).into(),
+        }
+    }
+
+    fn material(&self) -> StandardMaterial {
+        let color = match self {
+            Self::Normal => tailwind::RED_500,
+            Self::Golden => tailwind::AMBER_400,
+            Self::Shrink => tailwind::PURPLE_500,
+            Self::Speed => tailwind::LIME_400,
+            Self::Slow => tailwind::BLUE_500,
+            Self::Bonus => tailwind::PINK_400,
+        };
+        StandardMaterial {
+            base_color: Color::from(color),
+            perceptual_roughness: 1.0,
+            ..default()
+        }
+    }
+}
+
+#[derive(Component)]
+#[require(GameEntity)]
+pub struct Food(pub FoodKind);
+
+// the level tick the food disappears on if it hasn't been eaten
 #[derive(Component)]
-#[require(GameEntity, Mesh3d, MeshMaterial3d<StandardMaterial>)]
-struct Food;
+pub struct FoodExpiry(pub u64);
 
 fn on_add_food(
     trigger: Trigger<OnAdd, Food>,
-    food_material: Res<FoodMaterial>,
-    food_mesh: Res<FoodMesh>,
-    mut query: Query<(&mut Mesh3d, &mut MeshMaterial3d<StandardMaterial>)>,
+    query: Query<&Food>,
+    food_materials: Res<FoodMaterials>,
+    food_meshes: Res<FoodMeshes>,
+    mut commands: Commands,
 ) {
-    let (mut mesh, mut material) = query.get_mut(trigger.entity()).unwrap();
-    mesh.0 = food_mesh.0.clone();
-    material.0 = food_material.0.clone();
+    let kind = query.get(trigger.entity()).unwrap().0;
+    commands.entity(trigger.entity()).insert((
+        Mesh3d(food_meshes.0[&kind].clone()),
+        MeshMaterial3d(food_materials.0[&kind].clone()),
+    ));
+}
+
+fn insert_food_materials(mut materials: ResMut<Assets<StandardMaterial>>, mut commands: Commands) {
+    commands.insert_resource(FoodMaterials(HashMap::from_iter(
+        FoodKind::ALL.map(|kind| (kind, materials.add(kind.material()))),
+    )));
 }
 
-fn insert_food_material(mut materials: ResMut<Assets<StandardMaterial>>, mut commands: Commands) {
-    commands.insert_resource(FoodMaterial(materials.add(StandardMaterial {
-        base_color: Color::from(tailwind::RED_500),
-        perceptual_roughness: 1.0,
-        ..default()
-    })));
+fn insert_food_meshes(mut meshes: ResMut<Assets<Mesh>>, mut commands: Commands) {
+    commands.insert_resource(FoodMeshes(HashMap::from_iter(
+        FoodKind::ALL.map(|kind| (kind, meshes.add(kind.mesh()))),
+    )));
 }
 
-fn insert_food_mesh(mut meshes: ResMut<Assets<Mesh>>, mut commands: Commands) {
-    commands.insert_resource(FoodMesh(meshes.add(Sphere::new(0.5))));
+fn expire_food(
+    food_query: Query<(Entity, &FoodExpiry)>,
+    tick_query: Query<&LevelTick>,
+    mut commands: Commands,
+) {
+    let Ok(tick) = tick_query.get_single() else {
+        return;
+    };
+
+    for (entity, expiry) in food_query.iter() {
+        if tick.0 >= expiry.0 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// a magnetised head pulls the food around it a cell closer each move, but not through anything else
+fn attract_food(
+    snake_query: Query<
+        (Entity, &GridPosition, &SnakeEffects),
+        (With<SnakeHead>, Changed<GridPosition>),
+    >,
+    mut food_query: Query<(Entity, &mut GridPosition), (With<Food>, Without<SnakeHead>)>,
+    arena_query: Query<&ArenaSize>,
+    mut occupancy: ResMut<Occupancy>,
+    rules: Res<GameRules>,
+) {
+    let Some(arena_size) = arena_query.iter().next() else {
+        return;
+    };
+
+    for (snake_entity, snake_grid_position, effects) in snake_query.iter() {
+        if !effects.is_active(PowerUpKind::Magnet) {
+            continue;
+        }
+
+        for (food_entity, mut grid_position) in food_query.iter_mut() {
+            let offset = snake_grid_position.0 - grid_position.0;
+            if offset == IVec3::ZERO || offset.abs().max_element() > rules.magnet_radius {
+                continue;
+            }
+
+            // close the longer gap first, sliding along the other axis when that way is blocked
+            let steps = [IVec3::X * offset.x.signum(), IVec3::Z * offset.z.signum()];
+            let steps = match offset.x.abs() >= offset.z.abs() {
+                true => steps,
+                false => [steps[1], steps[0]],
+            };
+            let next = steps
+                .into_iter()
+                .filter(|step| *step != IVec3::ZERO)
+                .map(|step| grid_position.0 + step)
+                .find(|cell| {
+                    arena_size.contains(*cell)
+                        && occupancy
+                            .get(*cell)
+                            .iter()
+                            .all(|(other, _)| *other == snake_entity)
+                });
+
+            // the occupancy is kept current so two pieces of food never slide into one cell
+            if let Some(next) = next {
+                grid_position.0 = next;
+                occupancy.move_to(food_entity, next);
+            }
+        }
+    }
 }
 
 fn eat_food(
     mut snake_query: Query<
-        (&GridPosition, &mut SnakeBodyBuffer),
+        (Entity, &GridPosition, &mut SnakeBodyBuffer, &mut Score),
         (With<SnakeHead>, Changed<GridPosition>),
     >,
-    food_query: Query<(Entity, &GridPosition), (With<Food>, Without<SnakeHead>)>,
-    mut score_query: Query<&mut Score>,
+    food_query: Query<(Entity, &Food, &GridPosition), Without<SnakeHead>>,
+    tick_query: Query<&LevelTick>,
+    rules: Res<GameRules>,
     mut commands: Commands,
 ) {
-    for (snake_grid_position, mut buffer) in snake_query.iter_mut() {
-        for food_entity in food_query
+    let tick = tick_query.get_single().map_or(0, |tick| tick.0);
+    for (snake_entity, snake_grid_position, mut buffer, mut score) in snake_query.iter_mut() {
+        for (food_entity, food) in food_query
             .iter()
-            .filter_map(|(e, gp)| (gp == snake_grid_position).then(|| e))
+            .filter_map(|(e, food, gp)| (gp.0 == snake_grid_position.0).then_some((e, food)))
         {
             commands.entity(food_entity).despawn_recursive();
-            buffer.0 += 1; // extend the body
+            let food_rules = rules.food(food.0);
+
+            // extend or shrink the body
+            match food_rules.growth >= 0 {
+                true => buffer.0 += food_rules.growth as usize,
+                false => commands.trigger_targets(
+                    ShrinkSnake(food_rules.growth.unsigned_abs() as usize),
+                    snake_entity,
+                ),
+            }
 
-            // increment the score
-            for mut score in score_query.iter_mut() {
-                score.0 += 1;
+            if food_rules.effect_ticks > 0 {
+                commands.entity(snake_entity).insert(SnakeSpeedEffect {
+                    factor: food_rules.speed_factor,
+                    until: tick + food_rules.effect_ticks,
+                });
             }
+
+            score.0 += food_rules.score;
+            commands.trigger_targets(FoodEaten(food.0), snake_entity);
         }
     }
 }
 
 fn spawn_food(
-    grid_query: Query<&GridPosition>,
     arena_query: Query<&ArenaSize>,
     food_query: Query<&Food>,
     snake_query: Query<&SnakeHead>,
+    tick_query: Query<&LevelTick>,
+    occupancy: Res<Occupancy>,
+    rules: Res<GameRules>,
+    mut rng: ResMut<GameRng>,
     mut commands: Commands,
 ) {
-    if !food_query.is_empty() || snake_query.is_empty() {
+    // a normal food is always present, special ones only roll when it is replaced
+    if food_query.iter().any(|food| food.0 == FoodKind::Normal) || snake_query.is_empty() {
         return;
     }
 
@@ -96,17 +281,34 @@ fn spawn_food(
         return;
     };
 
-    let mut pool = Vec::with_capacity(arena_size.area() as usize);
-    for x in -arena_size.half_size()..=arena_size.half_size() {
-        for z in -arena_size.half_size()..=arena_size.half_size() {
-            let grid_position = GridPosition(IVec3::new(x, 0, z));
-            if grid_query.iter().all(|gp| gp != &grid_position) {
-                pool.push(grid_position);
-            }
-        }
+    let mut pool: Vec<_> = arena_size
+        .cells()
+        .filter(|cell| occupancy.is_empty(*cell))
+        .map(GridPosition)
+        .collect();
+
+    // the snake and obstacles can fill every cell
+    if pool.is_empty() {
+        return;
     }
 
-    let mut rng = thread_rng();
     let grid_position = pool.swap_remove(rng.gen_range(0..pool.len()));
-    commands.spawn((Food, grid_position));
+    commands.spawn((Food(FoodKind::Normal), grid_position));
+
+    let has_special = food_query.iter().any(|food| food.0 != FoodKind::Normal);
+    if has_special || pool.is_empty() || !rng.gen_bool(rules.special_food_chance.clamp(0.0, 1.0)) {
+        return;
+    }
+
+    let weights = FoodKind::SPECIAL.map(|kind| rules.food(kind).weight);
+    let Ok(distribution) = WeightedIndex::new(weights) else {
+        return;
+    };
+    let kind = FoodKind::SPECIAL[distribution.sample(&mut *rng)];
+    let grid_position = pool.swap_remove(rng.gen_range(0..pool.len()));
+    let mut food = commands.spawn((Food(kind), grid_position));
+    if let Some(lifetime) = rules.food(kind).lifetime {
+        let tick = tick_query.get_single().map_or(0, |tick| tick.0);
+        food.insert(FoodExpiry(tick + lifetime));
+    }
 }
@@ -1,20 +1,37 @@
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::snake::SnakeCollided;
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<GameState>()
-            .add_loading_state(
-                LoadingState::new(GameState::Load)
-                    .continue_to_state(GameState::Play)
-                    .load_collection::<GameFont>(),
-            )
+            .add_sub_state::<PlayState>()
+            .init_resource::<GameMode>()
             .add_observer(on_despawn_game_entities)
-            .add_observer(on_add_text_font)
-            .add_systems(PreStartup, insert_unit_cube_mesh)
-            .add_systems(OnEnter(GameState::Play), spawn_level);
+            .add_observer(on_spawn_level)
+            .add_observer(on_snake_collided)
+            .add_systems(OnEnter(GameState::Play), spawn_level)
+            .add_systems(OnExit(GameState::Play), despawn_level)
+            .add_systems(OnEnter(PlayState::Paused), pause_time)
+            .add_systems(OnExit(PlayState::Paused), unpause_time);
+    }
+}
+
+pub struct GameVisualPlugin;
+
+impl Plugin for GameVisualPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_loading_state(
+            LoadingState::new(GameState::Load)
+                .continue_to_state(GameState::Menu)
+                .load_collection::<GameFont>(),
+        )
+        .add_observer(on_add_text_font)
+        .add_systems(PreStartup, insert_unit_cube_mesh);
     }
 }
 
@@ -37,12 +54,66 @@ pub struct DespawnGameEntities;
 pub struct SpawnLevel;
 
 #[derive(States, Default, Debug, Hash, PartialEq, Eq, Clone)]
-enum GameState {
+pub enum GameState {
     #[default]
     Load,
+    Menu,
     Play,
 }
 
+#[derive(SubStates, Default, Debug, Hash, PartialEq, Eq, Clone)]
+#[source(GameState = GameState::Play)]
+pub enum PlayState {
+    #[default]
+    Running,
+    Paused,
+    Over,
+}
+
+#[derive(Resource, Serialize, Deserialize, Default, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum GameMode {
+    #[default]
+    Classic,
+    // leaving the arena re-enters it from the opposite edge
+    Wrap,
+    // handcrafted levels with obstacles, unlocked one after another
+    Campaign,
+    // two players on one keyboard, the last snake moving wins
+    Versus,
+}
+
+impl GameMode {
+    pub const ALL: [Self; 4] = [Self::Classic, Self::Wrap, Self::Campaign, Self::Versus];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Classic => "Classic",
+            Self::Wrap => "Wrap",
+            Self::Campaign => "Campaign",
+            Self::Versus => "Versus",
+        }
+    }
+
+    pub fn players(&self) -> usize {
+        match self {
+            Self::Versus => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn wraps(&self) -> bool {
+        matches!(self, Self::Wrap)
+    }
+
+    pub fn next(&self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|mode| mode == self)
+            .unwrap_or_default();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
 #[derive(Resource)]
 pub struct UnitCubeMesh(pub Handle<Mesh>);
 
@@ -80,3 +151,38 @@ fn on_despawn_game_entities(
 fn spawn_level(mut commands: Commands) {
     commands.trigger(SpawnLevel);
 }
+
+fn despawn_level(mut commands: Commands) {
+    commands.trigger(DespawnGameEntities);
+}
+
+// the title screen demo spawns levels too, but there's no play state to reset then
+fn on_spawn_level(
+    _: Trigger<SpawnLevel>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    if *state == GameState::Play {
+        next_state.set(PlayState::Running);
+    }
+}
+
+// crashes in the title screen demo don't end anything
+fn on_snake_collided(
+    _: Trigger<SnakeCollided>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    if *state == GameState::Play {
+        next_state.set(PlayState::Over);
+    }
+}
+
+// pausing virtual time stops fixed updates, freezing every gameplay system at once
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn unpause_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
@@ -1,15 +1,22 @@
-use bevy::prelude::*;
+use bevy::{color::palettes::tailwind, prelude::*};
 
 use crate::{
-    game::{DespawnGameEntities, GameEntity, SpawnLevel},
-    snake::SnakeCollided,
+    action::{ActionState, SnakeAction},
+    food::FoodKind,
+    game::{DespawnGameEntities, GameEntity, GameMode, GameState, PlayState, SpawnLevel},
+    high_score::HighScores,
+    level::{LevelTick, Score},
+    random::GameRng,
+    snake::{Player, SnakeBody, SnakeCrashed, SnakeHead},
+    stats::SnakeStats,
 };
 
 pub struct GameOverPlugin;
 
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
-        app.add_observer(on_snake_collided);
+        app.add_systems(OnEnter(PlayState::Over), spawn_game_over_ui)
+            .add_systems(Update, restart_on_action);
     }
 }
 
@@ -44,6 +51,60 @@ impl Title {
     }
 }
 
+#[derive(Component)]
+#[require(Text, TextFont(Self::text_font), TextColor(Self::text_color))]
+struct CauseLabel;
+
+impl CauseLabel {
+    fn text_font() -> TextFont {
+        TextFont::from_font_size(24.)
+    }
+
+    fn text_color() -> TextColor {
+        TextColor(Color::from(tailwind::RED_400))
+    }
+}
+
+#[derive(Component)]
+#[require(Text, TextFont(Self::text_font))]
+struct SeedLabel;
+
+impl SeedLabel {
+    fn text_font() -> TextFont {
+        TextFont::from_font_size(20.)
+    }
+}
+
+#[derive(Component)]
+#[require(Text, TextFont(Self::text_font))]
+struct ScoreLabel;
+
+impl ScoreLabel {
+    fn text_font() -> TextFont {
+        TextFont::from_font_size(20.)
+    }
+}
+
+#[derive(Component)]
+#[require(Text, TextFont(Self::text_font))]
+struct StatLabel;
+
+impl StatLabel {
+    fn text_font() -> TextFont {
+        TextFont::from_font_size(20.)
+    }
+}
+
+#[derive(Component)]
+#[require(Text, TextFont(Self::text_font))]
+struct HighScoreLabel;
+
+impl HighScoreLabel {
+    fn text_font() -> TextFont {
+        TextFont::from_font_size(20.)
+    }
+}
+
 #[derive(Component)]
 #[require(Button, Node(Self::node), BackgroundColor(Self::background_color))]
 struct RestartButton;
@@ -71,18 +132,198 @@ impl RestartButtonText {
     }
 }
 
-fn on_snake_collided(_: Trigger<SnakeCollided>, mut commands: Commands) {
+#[derive(Component)]
+#[require(Button, Node(Self::node), BackgroundColor(Self::background_color))]
+struct MenuButton;
+
+impl MenuButton {
+    fn node() -> Node {
+        Node {
+            padding: UiRect::all(Val::Px(10.)),
+            ..default()
+        }
+    }
+
+    fn background_color() -> BackgroundColor {
+        BackgroundColor(Color::WHITE.with_alpha(0.5))
+    }
+}
+
+#[derive(Component)]
+#[require(Text(Self::text))]
+struct MenuButtonText;
+
+impl MenuButtonText {
+    fn text() -> Text {
+        Text::new("Menu")
+    }
+}
+
+fn spawn_game_over_ui(
+    head_query: Query<
+        (&Player, &Score, &SnakeBody, &SnakeStats, Has<SnakeCrashed>),
+        With<SnakeHead>,
+    >,
+    tick_query: Query<&LevelTick>,
+    rng: Res<GameRng>,
+    mode: Res<GameMode>,
+    high_scores: Res<HighScores>,
+    time: Res<Time<Fixed>>,
+    mut commands: Commands,
+) {
+    // versus games declare the snake left moving as the winner
+    let versus = mode.players() > 1;
+    let survivors: Vec<_> = head_query
+        .iter()
+        .filter_map(|(player, .., crashed)| (!crashed).then_some(*player))
+        .collect();
+    let title = match survivors.as_slice() {
+        _ if !versus => Title::text(),
+        [winner] => Text::new(format!("{} wins", winner.name())),
+        _ => Text::new("Draw"),
+    };
+
     // spawn the game-over UI
     commands.spawn(GameOverUi).with_children(|cb| {
-        cb.spawn(Title);
+        cb.spawn((Title, title));
+        cb.spawn((SeedLabel, Text::new(format!("Seed {}", rng.seed()))));
+        let seconds = tick_query
+            .get_single()
+            .map_or(0.0, |tick| tick.0 as f32 * time.timestep().as_secs_f32())
+            as u32;
+        match versus {
+            true => {
+                // versus games aren't on the high score table, so there's no personal best
+                for (player, score, body, stats, _) in head_query.iter().sort::<&Player>() {
+                    let text = match stats.collision {
+                        Some(collision) => format!(
+                            "{} scored {} and {}",
+                            player.name(),
+                            score.0,
+                            collision.cause.description()
+                        ),
+                        None => format!("{} scored {}", player.name(), score.0),
+                    };
+                    cb.spawn((ScoreLabel, Text::new(text)));
+                    for line in stat_lines(body, stats, seconds) {
+                        cb.spawn((StatLabel, Text::new(line)));
+                    }
+                }
+            }
+            false => {
+                if let Some((_, score, body, stats, _)) = head_query.iter().next() {
+                    if let Some(collision) = stats.collision {
+                        cb.spawn((
+                            CauseLabel,
+                            Text::new(format!(
+                                "The snake {} at {}, {}",
+                                collision.cause.description(),
+                                collision.cell.x,
+                                collision.cell.z
+                            )),
+                        ));
+                    }
+
+                    cb.spawn((ScoreLabel, Text::new(format!("Score {}", score.0))));
+                    for line in stat_lines(body, stats, seconds) {
+                        cb.spawn((StatLabel, Text::new(line)));
+                    }
+                    cb.spawn((
+                        StatLabel,
+                        Text::new(personal_best_summary(score.0, *mode, &high_scores)),
+                    ));
+                }
+
+                for (i, entry) in high_scores.entries.iter().enumerate() {
+                    // highlight the entry this game just earned
+                    let color = match high_scores.latest == Some(i) {
+                        true => Color::from(tailwind::AMBER_400),
+                        false => Color::WHITE,
+                    };
+                    cb.spawn((
+                        HighScoreLabel,
+                        Text::new(entry.summary(i)),
+                        TextColor(color),
+                    ));
+                }
+            }
+        }
         cb.spawn(RestartButton)
             .observe(on_restart_button_click)
             .with_child(RestartButtonText);
+        cb.spawn(MenuButton)
+            .observe(on_menu_button_click)
+            .with_child(MenuButtonText);
     });
 }
 
+// how a snake's game went, for every snake on the game-over screen
+fn stat_lines(body: &SnakeBody, stats: &SnakeStats, seconds: u32) -> [String; 3] {
+    [
+        format!(
+            "Length {}  survived {}:{:02}  {} moves",
+            body.len() + 1,
+            seconds / 60,
+            seconds % 60,
+            stats.moves
+        ),
+        format!(
+            "Largest arena {}x{}",
+            stats.largest_arena.x, stats.largest_arena.y
+        ),
+        food_eaten_summary(stats),
+    ]
+}
+
+fn food_eaten_summary(stats: &SnakeStats) -> String {
+    let eaten: Vec<_> = FoodKind::ALL
+        .iter()
+        .filter(|kind| stats.eaten(**kind) > 0)
+        .map(|kind| format!("{} {}", stats.eaten(*kind), kind.name()))
+        .collect();
+    match eaten.is_empty() {
+        true => "No food eaten".to_string(),
+        false => format!("Ate {}", eaten.join(", ")),
+    }
+}
+
+// compares the score with the best earlier game in the same mode
+fn personal_best_summary(score: u32, mode: GameMode, high_scores: &HighScores) -> String {
+    let best = high_scores
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(i, entry)| entry.mode == mode && high_scores.latest != Some(*i))
+        .map(|(_, entry)| entry.score)
+        .max();
+    match best {
+        None => "No earlier score to beat".to_string(),
+        Some(best) if score > best => format!("New personal best, beating {best}"),
+        Some(best) if score == best => format!("Equalled your personal best of {best}"),
+        Some(best) => format!("{} short of your personal best of {best}", best - score),
+    }
+}
+
 fn on_restart_button_click(mut trigger: Trigger<Pointer<Click>>, mut commands: Commands) {
     trigger.propagate(false);
+    restart(&mut commands);
+}
+
+fn on_menu_button_click(
+    mut trigger: Trigger<Pointer<Click>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    trigger.propagate(false);
+    next_state.set(GameState::Menu);
+}
+
+fn restart_on_action(query: Query<&GameOverUi>, actions: Res<ActionState>, mut commands: Commands) {
+    if !query.is_empty() && actions.just_pressed(SnakeAction::Restart) {
+        restart(&mut commands);
+    }
+}
+
+fn restart(commands: &mut Commands) {
     commands.trigger(DespawnGameEntities);
     commands.trigger(SpawnLevel);
 }
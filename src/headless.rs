@@ -0,0 +1,37 @@
+use bevy::{app::AppExit, prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+use crate::{game::GameState, level::Score, random::GameRng, snake::SnakeCollided};
+
+// runs the rule plugins without a window or assets, must be added before them
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<StatesPlugin>() {
+            app.add_plugins(StatesPlugin);
+        }
+
+        // every update advances exactly one fixed timestep, regardless of wall-clock time
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(
+            Time::<Fixed>::default().timestep(),
+        ))
+        .add_observer(on_snake_collided)
+        .add_systems(OnEnter(GameState::Load), skip_loading);
+    }
+}
+
+fn skip_loading(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Play);
+}
+
+fn on_snake_collided(
+    trigger: Trigger<SnakeCollided>,
+    score_query: Query<&Score>,
+    rng: Res<GameRng>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if let Ok(score) = score_query.get(trigger.entity()) {
+        println!("seed {} score {}", rng.seed(), score.0);
+    }
+    exit.send(AppExit::Success);
+}
@@ -0,0 +1,203 @@
+use std::{
+    cmp::Reverse,
+    fs, io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{GameMode, GameState, SpawnLevel},
+    level::{LevelTick, Score},
+    random::GameRng,
+    snake::{SnakeBody, SnakeCollided},
+};
+
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load())
+            .add_observer(on_spawn_level)
+            .add_observer(on_snake_collided);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighScoreEntry {
+    pub score: u32,
+    pub length: u32,
+    pub duration_secs: f32,
+    pub timestamp: u64,
+    pub seed: u64,
+    pub mode: GameMode,
+}
+
+impl HighScoreEntry {
+    pub fn summary(&self, rank: usize) -> String {
+        let seconds = self.duration_secs as u32;
+        format!(
+            "{}. {}  length {}  {}:{:02}  {}  {}",
+            rank + 1,
+            self.score,
+            self.length,
+            seconds / 60,
+            seconds % 60,
+            format_date(self.timestamp),
+            self.mode.name(),
+        )
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+    // the index of the entry added by the last game, if it made the table
+    pub latest: Option<usize>,
+}
+
+impl HighScores {
+    pub const CAPACITY: usize = 10;
+
+    fn path() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_default()
+            .join("snake")
+            .join("high_scores.ron")
+    }
+
+    fn load() -> Self {
+        let contents = match fs::read_to_string(Self::path()) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                warn!("failed to read high scores: {e}");
+                return Self::default();
+            }
+        };
+
+        // every entry is on its own line, so a corrupt line only loses that entry
+        let mut entries: Vec<HighScoreEntry> = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match ron::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("skipping corrupt high score entry: {e}");
+                    None
+                }
+            })
+            .collect();
+        entries.sort_by_key(|entry| Reverse(entry.score));
+        entries.truncate(Self::CAPACITY);
+
+        Self {
+            entries,
+            latest: None,
+        }
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut contents = String::new();
+        for entry in self.entries.iter() {
+            let line =
+                ron::to_string(entry).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            contents.push_str(&line);
+            contents.push('\n');
+        }
+
+        // write to a temporary file first so a crash can't leave a half-written table
+        let temp_path = path.with_extension("ron.tmp");
+        fs::write(&temp_path, contents)?;
+        fs::rename(temp_path, path)
+    }
+
+    fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        // ties go below existing entries, the first to reach a score keeps the higher rank
+        let index = self
+            .entries
+            .iter()
+            .position(|existing| existing.score < entry.score)
+            .unwrap_or(self.entries.len());
+        if index >= Self::CAPACITY {
+            return None;
+        }
+
+        self.entries.insert(index, entry);
+        self.entries.truncate(Self::CAPACITY);
+        Some(index)
+    }
+}
+
+fn on_spawn_level(_: Trigger<SpawnLevel>, mut high_scores: ResMut<HighScores>) {
+    high_scores.latest = None;
+}
+
+fn on_snake_collided(
+    trigger: Trigger<SnakeCollided>,
+    score_query: Query<&Score>,
+    tick_query: Query<&LevelTick>,
+    body_query: Query<&SnakeBody>,
+    rng: Res<GameRng>,
+    mode: Res<GameMode>,
+    state: Res<State<GameState>>,
+    time: Res<Time<Fixed>>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    // versus games are scored against each other rather than the table, and the title screen
+    // demo isn't scored at all
+    if mode.players() > 1 || *state != GameState::Play {
+        return;
+    }
+
+    let head = trigger.entity();
+    let (Ok(score), Ok(tick)) = (score_query.get(head), tick_query.get_single()) else {
+        return;
+    };
+
+    let entry = HighScoreEntry {
+        score: score.0,
+        length: body_query.get(head).map_or(0, |body| body.len() as u32) + 1,
+        duration_secs: tick.0 as f32 * time.timestep().as_secs_f32(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        seed: rng.seed(),
+        mode: *mode,
+    };
+
+    high_scores.latest = high_scores.insert(entry);
+    if high_scores.latest.is_some() {
+        if let Err(e) = high_scores.save() {
+            error!("failed to save high scores: {e}");
+        }
+    }
+}
+
+// formats a unix timestamp as a yyyy-mm-dd date in UTC
+fn format_date(timestamp: u64) -> String {
+    // civil-from-days, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
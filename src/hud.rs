@@ -1,35 +1,43 @@
 use bevy::prelude::*;
 
 use crate::{
-    game::{GameEntity, SpawnLevel},
+    campaign::Campaign,
+    game::{GameEntity, GameMode, GameState, SpawnLevel},
     level::Score,
+    power_up::{PowerUpKind, SnakeEffects},
+    snake::{Player, SnakeSpeed},
 };
 
 pub struct HudPlugin;
 
 impl Plugin for HudPlugin {
     fn build(&self, app: &mut App) {
-        app.add_observer(on_spawn_level)
-            .add_systems(FixedPostUpdate, update_score_label);
+        app.add_observer(on_spawn_level).add_systems(
+            FixedPostUpdate,
+            (update_score_label, update_speed_label, update_effects_bar),
+        );
     }
 }
 
 #[derive(Component)]
-#[require(
-    GameEntity,
-    Text(Self::text),
-    Node(Self::node),
-    TextFont(Self::text_font)
-)]
-struct ScoreLabel;
+#[require(GameEntity, Text(Self::text), TextFont(Self::text_font))]
+struct ScoreLabel(Player);
 
 impl ScoreLabel {
     fn text() -> Text {
         Text::new("0")
     }
 
-    fn node() -> Node {
+    // player two's score sits under player one's
+    fn node(&self) -> Node {
+        let row = Player::ALL
+            .iter()
+            .position(|p| *p == self.0)
+            .unwrap_or_default();
         Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(row as f32 * 56.),
+            left: Val::Px(0.),
             margin: UiRect::all(Val::Px(16.)),
             ..default()
         }
@@ -40,17 +48,177 @@ impl ScoreLabel {
     }
 }
 
-fn on_spawn_level(_: Trigger<SpawnLevel>, mut commands: Commands) {
-    commands.spawn(ScoreLabel);
+#[derive(Component)]
+#[require(GameEntity, Text, TextFont(Self::text_font))]
+struct SpeedLabel(Player);
+
+impl SpeedLabel {
+    // player two's speed sits under player one's
+    fn node(&self) -> Node {
+        let row = Player::ALL
+            .iter()
+            .position(|p| *p == self.0)
+            .unwrap_or_default();
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(row as f32 * 32.),
+            right: Val::Px(0.),
+            margin: UiRect::all(Val::Px(16.)),
+            ..default()
+        }
+    }
+
+    fn text_font() -> TextFont {
+        TextFont::from_font_size(24.)
+    }
+
+    // versus games show whose speed is whose
+    fn speed_text(&self, level: u32, players: usize) -> Text {
+        match players {
+            1 => Text::new(format!("Speed {}", level + 1)),
+            _ => Text::new(format!("{} speed {}", self.0.name(), level + 1)),
+        }
+    }
+}
+
+// the active power-ups, each with an icon and the moves it has left
+#[derive(Component)]
+#[require(GameEntity)]
+struct EffectsBar(Player);
+
+impl EffectsBar {
+    // player one's bar sits in the bottom left corner and player two's in the bottom right
+    fn node(&self) -> Node {
+        let mut node = Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(0.),
+            margin: UiRect::all(Val::Px(16.)),
+            column_gap: Val::Px(16.),
+            ..default()
+        };
+        match self.0 {
+            Player::One => node.left = Val::Px(0.),
+            Player::Two => node.right = Val::Px(0.),
+        }
+        node
+    }
+}
+
+#[derive(Component)]
+#[require(Node(Self::node), BackgroundColor)]
+struct EffectIcon;
+
+impl EffectIcon {
+    fn node() -> Node {
+        Node {
+            width: Val::Px(20.),
+            height: Val::Px(20.),
+            margin: UiRect::right(Val::Px(6.)),
+            ..default()
+        }
+    }
+}
+
+#[derive(Component)]
+#[require(Text, TextFont(Self::text_font))]
+struct EffectLabel;
+
+impl EffectLabel {
+    fn text_font() -> TextFont {
+        TextFont::from_font_size(20.)
+    }
+}
+
+// the title screen demo has no hud
+fn on_spawn_level(
+    _: Trigger<SpawnLevel>,
+    state: Res<State<GameState>>,
+    mode: Res<GameMode>,
+    mut commands: Commands,
+) {
+    if *state != GameState::Play {
+        return;
+    }
+    for player in &Player::ALL[..mode.players()] {
+        let score_label = ScoreLabel(*player);
+        let speed_label = SpeedLabel(*player);
+        let effects_bar = EffectsBar(*player);
+        commands.spawn((score_label.node(), score_label));
+        commands.spawn((
+            speed_label.node(),
+            speed_label.speed_text(0, mode.players()),
+            speed_label,
+        ));
+        commands.spawn((effects_bar.node(), effects_bar));
+    }
 }
 
 fn update_score_label(
-    score_query: Query<&Score, Changed<Score>>,
-    mut label_query: Query<&mut Text, With<ScoreLabel>>,
+    score_query: Query<(&Score, &Player), Changed<Score>>,
+    mut label_query: Query<(&ScoreLabel, &mut Text)>,
+    mode: Res<GameMode>,
+    campaign: Res<Campaign>,
+) {
+    let layout = campaign.layout(*mode);
+    for (score, player) in score_query.iter() {
+        for (_, mut text) in label_query
+            .iter_mut()
+            .filter(|(label, _)| label.0 == *player)
+        {
+            // campaign levels show how far the player is from completing them, versus games show
+            // whose score is whose
+            text.0 = match (layout, mode.players()) {
+                (Some(layout), _) => format!("{}/{}", score.0, layout.target_score),
+                (None, 1) => score.0.to_string(),
+                (None, _) => format!("{} {}", player.name(), score.0),
+            };
+        }
+    }
+}
+
+fn update_speed_label(
+    speed_query: Query<(&SnakeSpeed, &Player), Changed<SnakeSpeed>>,
+    mut label_query: Query<(&SpeedLabel, &mut Text)>,
+    mode: Res<GameMode>,
+) {
+    for (speed, player) in speed_query.iter() {
+        for (label, mut text) in label_query
+            .iter_mut()
+            .filter(|(label, _)| label.0 == *player)
+        {
+            *text = label.speed_text(speed.0, mode.players());
+        }
+    }
+}
+
+fn update_effects_bar(
+    effects_query: Query<(&SnakeEffects, &Player), Changed<SnakeEffects>>,
+    bar_query: Query<(Entity, &EffectsBar)>,
+    mut commands: Commands,
 ) {
-    for score in score_query.iter() {
-        for mut text in label_query.iter_mut() {
-            text.0 = score.0.to_string();
+    for (effects, player) in effects_query.iter() {
+        for (entity, _) in bar_query.iter().filter(|(_, bar)| bar.0 == *player) {
+            commands
+                .entity(entity)
+                .despawn_descendants()
+                .with_children(|cb| {
+                    for kind in PowerUpKind::ALL {
+                        if !effects.is_active(kind) {
+                            continue;
+                        }
+                        cb.spawn(Node {
+                            align_items: AlignItems::Center,
+                            ..default()
+                        })
+                        .with_children(|cb| {
+                            cb.spawn((EffectIcon, BackgroundColor(kind.color())));
+                            cb.spawn((
+                                EffectLabel,
+                                Text::new(format!("{} {}", kind.name(), effects.remaining(kind))),
+                            ));
+                        });
+                    }
+                });
         }
     }
 }
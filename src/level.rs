@@ -13,9 +13,18 @@ use crate::{
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(on_spawn_level)
+            .add_systems(FixedLast, advance_level_tick);
+    }
+}
+
+pub struct LevelVisualPlugin;
+
+impl Plugin for LevelVisualPlugin {
     fn build(&self, app: &mut App) {
         app.configure_sets(FixedUpdate, LevelSet.after(ArenaSet))
-            .add_observer(on_spawn_level)
+            .add_observer(on_spawn_level_view)
             .add_systems(FixedUpdate, resize_view.in_set(LevelSet))
             .add_systems(
                 Update,
@@ -78,25 +87,44 @@ impl LevelLight {
     }
 }
 
+// the score of the snake head it is on
 #[derive(Component, Default)]
 #[require(GameEntity)]
 pub struct Score(pub u32);
 
+// the number of fixed updates completed since the level spawned
+#[derive(Component, Default)]
+#[require(GameEntity)]
+pub struct LevelTick(pub u64);
+
 fn on_spawn_level(_: Trigger<SpawnLevel>, mut commands: Commands) {
+    commands.spawn(LevelTick::default());
+}
+
+fn advance_level_tick(mut query: Query<&mut LevelTick>) {
+    for mut tick in query.iter_mut() {
+        tick.0 += 1;
+    }
+}
+
+fn on_spawn_level_view(_: Trigger<SpawnLevel>, mut commands: Commands) {
     commands.spawn(LevelCamera);
     commands.spawn(LevelLight);
-    commands.spawn(Score::default());
 }
 
 fn resize_view(
     arena_query: Query<&ArenaSize, Changed<ArenaSize>>,
-    camera_query: Query<(Entity, &Projection), With<LevelCamera>>,
+    mut camera_query: Query<(Entity, &Projection, &mut Transform), With<LevelCamera>>,
     mut commands: Commands,
 ) {
     const OFFSET: f32 = 4.0;
     for arena_size in arena_query.iter() {
-        let new_viewport_height = arena_size.0 as f32 + OFFSET;
-        for (entity, projection) in camera_query.iter() {
+        // fit the larger side of the bounding box and look at its centre
+        let new_viewport_height = arena_size.width.max(arena_size.height) as f32 + OFFSET;
+        let centre = (arena_size.min() + arena_size.max()).as_vec3() * 0.5;
+        for (entity, projection, mut transform) in camera_query.iter_mut() {
+            transform.translation = LevelCamera::transform().translation + centre;
+
             if let Projection::Orthographic(projection) = projection {
                 if let ScalingMode::FixedVertical { viewport_height } = projection.scaling_mode {
                     commands.entity(entity).insert(Animator::new(Tween::new(
@@ -0,0 +1,100 @@
+// bevy systems routinely trip these lints
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+pub mod action;
+pub mod arena;
+pub mod bot;
+pub mod campaign;
+pub mod control;
+pub mod environment;
+pub mod food;
+pub mod game;
+pub mod game_over;
+pub mod grid;
+pub mod headless;
+pub mod high_score;
+pub mod hud;
+pub mod level;
+pub mod menu;
+pub mod occupancy;
+pub mod pause;
+pub mod power_up;
+pub mod random;
+pub mod replay;
+pub mod rules;
+pub mod snake;
+pub mod stats;
+
+use action::ActionPlugin;
+use arena::{ArenaPlugin, ArenaVisualPlugin};
+use bevy::app::{PluginGroup, PluginGroupBuilder};
+use bevy_tweening::TweeningPlugin;
+use bot::BotPlugin;
+use campaign::CampaignPlugin;
+use control::ControlPlugin;
+use food::{FoodPlugin, FoodVisualPlugin};
+use game::{GamePlugin, GameVisualPlugin};
+use game_over::GameOverPlugin;
+use grid::GridPlugin;
+use high_score::HighScorePlugin;
+use hud::HudPlugin;
+use level::{LevelPlugin, LevelVisualPlugin};
+use menu::MenuPlugin;
+use occupancy::OccupancyPlugin;
+use pause::PausePlugin;
+use power_up::{PowerUpPlugin, PowerUpVisualPlugin};
+use random::RandomPlugin;
+use replay::ReplayPlugin;
+use rules::RulesPlugin;
+use snake::{SnakePlugin, SnakeVisualPlugin};
+use stats::StatsPlugin;
+
+pub use game::{DespawnGameEntities, SpawnLevel};
+pub use snake::{SnakeCollided, SnakeHead};
+
+// the full game, expects `DefaultPlugins` to already be added
+pub struct SnakeGamePlugins;
+
+impl PluginGroup for SnakeGamePlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add_group(SnakeRulesPlugins)
+            .add(TweeningPlugin)
+            .add(ActionPlugin)
+            .add(HighScorePlugin)
+            .add(GameVisualPlugin)
+            .add(LevelVisualPlugin)
+            .add(HudPlugin)
+            .add(ArenaVisualPlugin)
+            .add(SnakeVisualPlugin)
+            .add(GridPlugin)
+            .add(FoodVisualPlugin)
+            .add(PowerUpVisualPlugin)
+            .add(MenuPlugin)
+            .add(GameOverPlugin)
+            .add(PausePlugin)
+    }
+}
+
+// only the game rules, runs headless alongside `MinimalPlugins` and `HeadlessPlugin`
+pub struct SnakeRulesPlugins;
+
+impl PluginGroup for SnakeRulesPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(GamePlugin)
+            .add(RulesPlugin)
+            .add(RandomPlugin)
+            .add(CampaignPlugin)
+            .add(ReplayPlugin)
+            .add(LevelPlugin)
+            .add(ArenaPlugin)
+            .add(OccupancyPlugin)
+            .add(SnakePlugin)
+            .add(BotPlugin)
+            .add(ControlPlugin)
+            .add(FoodPlugin)
+            .add(PowerUpPlugin)
+            .add(StatsPlugin)
+    }
+}
@@ -0,0 +1,350 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    action::{InputBindings, SnakeAction},
+    bot::Opponent,
+    campaign::Campaign,
+    game::{DespawnGameEntities, GameMode, GameState, SpawnLevel},
+    high_score::HighScores,
+    snake::SnakeCrashed,
+};
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<MenuState>()
+            .add_systems(OnEnter(GameState::Menu), (spawn_menu_camera, spawn_demo))
+            .add_systems(
+                OnExit(GameState::Menu),
+                (despawn_menu::<MenuCamera>, despawn_demo),
+            )
+            .add_systems(OnEnter(MenuState::Title), spawn_title_page)
+            .add_systems(OnEnter(MenuState::Settings), spawn_settings_page)
+            .add_systems(OnEnter(MenuState::HighScores), spawn_high_scores_page)
+            .add_systems(OnExit(MenuState::Title), despawn_menu::<MenuUi>)
+            .add_systems(OnExit(MenuState::Settings), despawn_menu::<MenuUi>)
+            .add_systems(OnExit(MenuState::HighScores), despawn_menu::<MenuUi>)
+            .add_systems(
+                Update,
+                (
+                    update_mode_label.run_if(resource_changed::<GameMode>),
+                    update_level_button
+                        .run_if(resource_changed::<GameMode>.or(resource_changed::<Campaign>)),
+                    update_opponent_button
+                        .run_if(resource_changed::<GameMode>.or(resource_changed::<Opponent>)),
+                    restart_demo.run_if(in_state(GameState::Menu)),
+                ),
+            );
+    }
+}
+
+#[derive(SubStates, Default, Debug, Hash, PartialEq, Eq, Clone)]
+#[source(GameState = GameState::Menu)]
+pub enum MenuState {
+    #[default]
+    Title,
+    Settings,
+    HighScores,
+}
+
+// drawn over the demo playing behind the menu
+#[derive(Component)]
+#[require(Camera2d, Camera(Self::camera))]
+struct MenuCamera;
+
+impl MenuCamera {
+    fn camera() -> Camera {
+        Camera {
+            order: 1,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        }
+    }
+}
+
+#[derive(Component)]
+#[require(Node(Self::node))]
+struct MenuUi;
+
+impl MenuUi {
+    fn node() -> Node {
+        Node {
+            display: Display::Grid,
+            align_self: AlignSelf::Center,
+            justify_self: JustifySelf::Center,
+            justify_items: JustifyItems::Center,
+            row_gap: Val::Px(5.),
+            ..default()
+        }
+    }
+}
+
+#[derive(Component)]
+#[require(Text, TextFont(Self::text_font))]
+struct Title;
+
+impl Title {
+    fn text_font() -> TextFont {
+        TextFont::from_font_size(64.)
+    }
+}
+
+#[derive(Component)]
+#[require(Text, TextFont(Self::text_font))]
+struct MenuLabel;
+
+impl MenuLabel {
+    fn text_font() -> TextFont {
+        TextFont::from_font_size(20.)
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+#[require(Button, Node(Self::node), BackgroundColor(Self::background_color))]
+enum MenuButton {
+    Play,
+    Mode,
+    Level,
+    Opponent,
+    Settings,
+    HighScores,
+    Quit,
+    Back,
+}
+
+impl MenuButton {
+    fn node() -> Node {
+        Node {
+            padding: UiRect::all(Val::Px(10.)),
+            ..default()
+        }
+    }
+
+    fn background_color() -> BackgroundColor {
+        BackgroundColor(Color::WHITE.with_alpha(0.5))
+    }
+
+    fn text(&self) -> Text {
+        Text::new(match self {
+            Self::Play => "Play",
+            Self::Mode => "Mode",
+            Self::Level => "Level",
+            Self::Opponent => "Opponent",
+            Self::Settings => "Settings",
+            Self::HighScores => "High Scores",
+            Self::Quit => "Quit",
+            Self::Back => "Back",
+        })
+    }
+}
+
+#[derive(Component)]
+struct ModeLabel;
+
+#[derive(Component)]
+struct LevelLabel;
+
+#[derive(Component)]
+struct OpponentLabel;
+
+fn spawn_menu_camera(mut commands: Commands) {
+    commands.spawn(MenuCamera);
+}
+
+fn despawn_menu<C: Component>(query: Query<Entity, With<C>>, mut commands: Commands) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn spawn_button(cb: &mut ChildBuilder, button: MenuButton) {
+    let text = button.text();
+    cb.spawn(button)
+        .observe(on_menu_button_click)
+        .with_child(text);
+}
+
+fn spawn_title_page(
+    mode: Res<GameMode>,
+    campaign: Res<Campaign>,
+    opponent: Res<Opponent>,
+    mut commands: Commands,
+) {
+    commands.spawn(MenuUi).with_children(|cb| {
+        cb.spawn((Title, Text::new("Snake")));
+        spawn_button(cb, MenuButton::Play);
+        cb.spawn(MenuButton::Mode)
+            .observe(on_menu_button_click)
+            .with_child((ModeLabel, mode_text(&mode)));
+        cb.spawn((MenuButton::Level, level_button_node(&mode)))
+            .observe(on_menu_button_click)
+            .with_child((LevelLabel, level_text(&campaign)));
+        cb.spawn((MenuButton::Opponent, opponent_button_node(&mode)))
+            .observe(on_menu_button_click)
+            .with_child((OpponentLabel, opponent_text(&opponent)));
+        spawn_button(cb, MenuButton::Settings);
+        spawn_button(cb, MenuButton::HighScores);
+        spawn_button(cb, MenuButton::Quit);
+    });
+}
+
+fn spawn_settings_page(bindings: Res<InputBindings>, mut commands: Commands) {
+    commands.spawn(MenuUi).with_children(|cb| {
+        cb.spawn((Title, Text::new("Controls")));
+        for action in SnakeAction::ALL {
+            let labels: Vec<_> = bindings
+                .0
+                .get(&action)
+                .into_iter()
+                .flatten()
+                .map(|binding| binding.label())
+                .collect();
+            cb.spawn((
+                MenuLabel,
+                Text::new(format!("{}: {}", action.name(), labels.join(", "))),
+            ));
+        }
+        spawn_button(cb, MenuButton::Back);
+    });
+}
+
+fn spawn_high_scores_page(high_scores: Res<HighScores>, mut commands: Commands) {
+    commands.spawn(MenuUi).with_children(|cb| {
+        cb.spawn((Title, Text::new("High Scores")));
+        if high_scores.entries.is_empty() {
+            cb.spawn((MenuLabel, Text::new("No high scores yet")));
+        }
+        for (i, entry) in high_scores.entries.iter().enumerate() {
+            cb.spawn((MenuLabel, Text::new(entry.summary(i))));
+        }
+        spawn_button(cb, MenuButton::Back);
+    });
+}
+
+fn mode_text(mode: &GameMode) -> Text {
+    Text::new(format!("Mode: {}", mode.name()))
+}
+
+fn update_mode_label(mode: Res<GameMode>, mut query: Query<&mut Text, With<ModeLabel>>) {
+    for mut text in query.iter_mut() {
+        *text = mode_text(&mode);
+    }
+}
+
+// the level button is only shown while the campaign mode is selected
+fn level_button_node(mode: &GameMode) -> Node {
+    Node {
+        display: match mode {
+            GameMode::Campaign => Display::Flex,
+            _ => Display::None,
+        },
+        ..MenuButton::node()
+    }
+}
+
+fn level_text(campaign: &Campaign) -> Text {
+    Text::new(match campaign.levels.get(campaign.current) {
+        Some(layout) => format!("Level {}: {}", campaign.current + 1, layout.name),
+        None => "No levels".into(),
+    })
+}
+
+fn update_level_button(
+    mode: Res<GameMode>,
+    campaign: Res<Campaign>,
+    mut button_query: Query<(&MenuButton, &mut Node)>,
+    mut label_query: Query<&mut Text, With<LevelLabel>>,
+) {
+    for (button, mut node) in button_query.iter_mut() {
+        if let MenuButton::Level = button {
+            *node = level_button_node(&mode);
+        }
+    }
+    for mut text in label_query.iter_mut() {
+        *text = level_text(&campaign);
+    }
+}
+
+// the opponent button is only shown while the versus mode is selected
+fn opponent_button_node(mode: &GameMode) -> Node {
+    Node {
+        display: match mode {
+            GameMode::Versus => Display::Flex,
+            _ => Display::None,
+        },
+        ..MenuButton::node()
+    }
+}
+
+fn opponent_text(opponent: &Opponent) -> Text {
+    Text::new(format!("Opponent: {}", opponent.name()))
+}
+
+fn update_opponent_button(
+    mode: Res<GameMode>,
+    opponent: Res<Opponent>,
+    mut button_query: Query<(&MenuButton, &mut Node)>,
+    mut label_query: Query<&mut Text, With<OpponentLabel>>,
+) {
+    for (button, mut node) in button_query.iter_mut() {
+        if let MenuButton::Opponent = button {
+            *node = opponent_button_node(&mode);
+        }
+    }
+    for mut text in label_query.iter_mut() {
+        *text = opponent_text(&opponent);
+    }
+}
+
+// bots play the selected mode behind the menu until the player starts a game
+fn spawn_demo(mut commands: Commands) {
+    commands.trigger(SpawnLevel);
+}
+
+fn despawn_demo(mut commands: Commands) {
+    commands.trigger(DespawnGameEntities);
+}
+
+// the demo starts over when a bot crashes or another mode is selected
+fn restart_demo(
+    crashed_query: Query<(), With<SnakeCrashed>>,
+    mode: Res<GameMode>,
+    mut commands: Commands,
+) {
+    if crashed_query.is_empty() && !mode.is_changed() {
+        return;
+    }
+
+    commands.trigger(DespawnGameEntities);
+    commands.trigger(SpawnLevel);
+}
+
+fn on_menu_button_click(
+    mut trigger: Trigger<Pointer<Click>>,
+    query: Query<&MenuButton>,
+    mut mode: ResMut<GameMode>,
+    mut campaign: ResMut<Campaign>,
+    mut opponent: ResMut<Opponent>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    trigger.propagate(false);
+    let Ok(button) = query.get(trigger.entity()) else {
+        return;
+    };
+
+    match button {
+        MenuButton::Play => next_game_state.set(GameState::Play),
+        MenuButton::Mode => *mode = mode.next(),
+        MenuButton::Level => campaign.select_next(),
+        MenuButton::Opponent => *opponent = opponent.next(),
+        MenuButton::Settings => next_menu_state.set(MenuState::Settings),
+        MenuButton::HighScores => next_menu_state.set(MenuState::HighScores),
+        MenuButton::Quit => {
+            exit.send(AppExit::Success);
+        }
+        MenuButton::Back => next_menu_state.set(MenuState::Title),
+    }
+}
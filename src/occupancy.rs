@@ -0,0 +1,146 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    arena::Obstacle,
+    food::{Food, FoodSet},
+    grid::GridPosition,
+    power_up::{PowerUp, PowerUpSet},
+    snake::{SnakeBodySegment, SnakeHead, SnakeSet},
+};
+
+pub struct OccupancyPlugin;
+
+impl Plugin for OccupancyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Occupancy>()
+            .configure_sets(
+                FixedUpdate,
+                OccupancySet
+                    .after(SnakeSet)
+                    .before(FoodSet)
+                    .before(PowerUpSet),
+            )
+            .add_observer(on_insert_grid_position)
+            .add_observer(on_replace_grid_position)
+            .add_systems(FixedUpdate, update_occupancy.in_set(OccupancySet));
+    }
+}
+
+#[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
+pub struct OccupancySet;
+
+// what stands on a cell
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Occupant {
+    SnakeHead,
+    SnakeBody { head: Entity },
+    Obstacle,
+    Food,
+    PowerUp,
+    Other,
+}
+
+impl Occupant {
+    // snakes crash into these, food and power-ups are picked up instead
+    pub fn is_solid(&self) -> bool {
+        matches!(
+            self,
+            Self::SnakeHead | Self::SnakeBody { .. } | Self::Obstacle
+        )
+    }
+}
+
+// every entity with a grid position, looked up by cell
+#[derive(Resource, Default, Debug)]
+pub struct Occupancy {
+    cells: HashMap<IVec3, Vec<(Entity, Occupant)>>,
+    entities: HashMap<Entity, (IVec3, Occupant)>,
+}
+
+impl Occupancy {
+    pub fn get(&self, cell: IVec3) -> &[(Entity, Occupant)] {
+        self.cells.get(&cell).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn is_empty(&self, cell: IVec3) -> bool {
+        self.get(cell).is_empty()
+    }
+
+    pub fn is_solid(&self, cell: IVec3) -> bool {
+        self.get(cell)
+            .iter()
+            .any(|(_, occupant)| occupant.is_solid())
+    }
+
+    pub fn insert(&mut self, entity: Entity, cell: IVec3, occupant: Occupant) {
+        self.remove(entity);
+        self.cells.entry(cell).or_default().push((entity, occupant));
+        self.entities.insert(entity, (cell, occupant));
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        let Some((cell, _)) = self.entities.remove(&entity) else {
+            return;
+        };
+        if let Some(occupants) = self.cells.get_mut(&cell) {
+            occupants.retain(|(other, _)| *other != entity);
+            if occupants.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    // moves an entity that is already on the grid, keeping what kind of occupant it is
+    pub fn move_to(&mut self, entity: Entity, cell: IVec3) {
+        if let Some(&(previous, occupant)) = self.entities.get(&entity) {
+            if previous != cell {
+                self.insert(entity, cell, occupant);
+            }
+        }
+    }
+}
+
+fn on_insert_grid_position(
+    trigger: Trigger<OnInsert, GridPosition>,
+    query: Query<(
+        &GridPosition,
+        Has<SnakeHead>,
+        Option<&SnakeBodySegment>,
+        Has<Obstacle>,
+        Has<Food>,
+        Has<PowerUp>,
+    )>,
+    mut occupancy: ResMut<Occupancy>,
+) {
+    let Ok((grid_position, head, segment, obstacle, food, power_up)) = query.get(trigger.entity())
+    else {
+        return;
+    };
+
+    let occupant = match (head, segment, obstacle, food, power_up) {
+        (true, ..) => Occupant::SnakeHead,
+        (_, Some(segment), ..) => Occupant::SnakeBody { head: segment.head },
+        (_, _, true, ..) => Occupant::Obstacle,
+        (_, _, _, true, _) => Occupant::Food,
+        (.., true) => Occupant::PowerUp,
+        _ => Occupant::Other,
+    };
+    occupancy.insert(trigger.entity(), grid_position.0, occupant);
+}
+
+fn on_replace_grid_position(
+    trigger: Trigger<OnReplace, GridPosition>,
+    mut occupancy: ResMut<Occupancy>,
+) {
+    occupancy.remove(trigger.entity());
+}
+
+// entities moved in place rather than having their position inserted again
+fn update_occupancy(
+    query: Query<(Entity, &GridPosition), Changed<GridPosition>>,
+    mut occupancy: ResMut<Occupancy>,
+) {
+    for (entity, grid_position) in query.iter() {
+        occupancy.move_to(entity, grid_position.0);
+    }
+}
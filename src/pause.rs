@@ -0,0 +1,155 @@
+use bevy::{app::AppExit, prelude::*, window::WindowFocused};
+
+use crate::{
+    action::{ActionState, SnakeAction},
+    game::{DespawnGameEntities, PlayState, SpawnLevel},
+};
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                toggle_pause
+                    .run_if(resource_exists::<ActionState>)
+                    .run_if(in_state(PlayState::Running).or(in_state(PlayState::Paused))),
+                pause_on_focus_lost.run_if(in_state(PlayState::Running)),
+            ),
+        )
+        .add_systems(OnEnter(PlayState::Paused), spawn_pause_ui)
+        .add_systems(OnExit(PlayState::Paused), despawn_pause_ui);
+    }
+}
+
+#[derive(Component)]
+#[require(Node(Self::node), BackgroundColor(Self::background_color))]
+struct PauseUi;
+
+impl PauseUi {
+    fn node() -> Node {
+        Node {
+            display: Display::Grid,
+            align_self: AlignSelf::Center,
+            justify_self: JustifySelf::Center,
+            justify_items: JustifyItems::Center,
+            row_gap: Val::Px(5.),
+            padding: UiRect::all(Val::Px(20.)),
+            ..default()
+        }
+    }
+
+    fn background_color() -> BackgroundColor {
+        BackgroundColor(Color::BLACK.with_alpha(0.5))
+    }
+}
+
+#[derive(Component)]
+#[require(Text(Self::text), TextFont(Self::text_font))]
+struct Title;
+
+impl Title {
+    fn text() -> Text {
+        Text::new("Paused")
+    }
+
+    fn text_font() -> TextFont {
+        TextFont::from_font_size(64.)
+    }
+}
+
+#[derive(Component)]
+#[require(Button, Node(Self::node), BackgroundColor(Self::background_color))]
+enum PauseButton {
+    Resume,
+    Restart,
+    Quit,
+}
+
+impl PauseButton {
+    fn node() -> Node {
+        Node {
+            padding: UiRect::all(Val::Px(10.)),
+            ..default()
+        }
+    }
+
+    fn background_color() -> BackgroundColor {
+        BackgroundColor(Color::WHITE.with_alpha(0.5))
+    }
+
+    fn text(&self) -> Text {
+        Text::new(match self {
+            Self::Resume => "Resume",
+            Self::Restart => "Restart",
+            Self::Quit => "Quit",
+        })
+    }
+}
+
+fn toggle_pause(
+    actions: Res<ActionState>,
+    state: Res<State<PlayState>>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    if !actions.just_pressed(SnakeAction::Pause) {
+        return;
+    }
+
+    next_state.set(match state.get() {
+        PlayState::Paused => PlayState::Running,
+        _ => PlayState::Paused,
+    });
+}
+
+fn pause_on_focus_lost(
+    mut events: EventReader<WindowFocused>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    if events.read().any(|event| !event.focused) {
+        next_state.set(PlayState::Paused);
+    }
+}
+
+fn spawn_pause_ui(mut commands: Commands) {
+    commands.spawn(PauseUi).with_children(|cb| {
+        cb.spawn(Title);
+        for button in [PauseButton::Resume, PauseButton::Restart, PauseButton::Quit] {
+            let text = button.text();
+            cb.spawn(button)
+                .observe(on_pause_button_click)
+                .with_child(text);
+        }
+    });
+}
+
+fn despawn_pause_ui(query: Query<Entity, With<PauseUi>>, mut commands: Commands) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn on_pause_button_click(
+    mut trigger: Trigger<Pointer<Click>>,
+    query: Query<&PauseButton>,
+    mut next_state: ResMut<NextState<PlayState>>,
+    mut exit: EventWriter<AppExit>,
+    mut commands: Commands,
+) {
+    trigger.propagate(false);
+    let Ok(button) = query.get(trigger.entity()) else {
+        return;
+    };
+
+    match button {
+        PauseButton::Resume => next_state.set(PlayState::Running),
+        PauseButton::Restart => {
+            commands.trigger(DespawnGameEntities);
+            commands.trigger(SpawnLevel);
+        }
+        PauseButton::Quit => {
+            exit.send(AppExit::Success);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use bevy::{color::palettes::tailwind, prelude::*, utils::HashMap};
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arena::ArenaSize,
+    game::GameEntity,
+    grid::{GridPosition, GridSet},
+    level::LevelTick,
+    occupancy::Occupancy,
+    random::GameRng,
+    rules::GameRules,
+    snake::{SnakeHead, SnakeSet},
+};
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(FixedUpdate, PowerUpSet.after(SnakeSet).before(GridSet))
+            .add_systems(
+                FixedUpdate,
+                (expire_power_ups, collect_power_ups, spawn_power_ups)
+                    .chain()
+                    .in_set(PowerUpSet),
+            );
+    }
+}
+
+pub struct PowerUpVisualPlugin;
+
+impl Plugin for PowerUpVisualPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(on_add_power_up)
+            .add_systems(PreStartup, insert_power_up_assets);
+    }
+}
+
+#[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
+pub struct PowerUpSet;
+
+#[derive(Resource)]
+struct PowerUpAssets(HashMap<PowerUpKind, (Handle<Mesh>, Handle<StandardMaterial>)>);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PowerUpKind {
+    // the snake passes through its own body
+    Ghost,
+    // food near the head slides a cell closer every move
+    Magnet,
+    // the next collision stops the snake instead of ending the game
+    Shield,
+}
+
+impl PowerUpKind {
+    pub const ALL: [Self; 3] = [Self::Ghost, Self::Magnet, Self::Shield];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ghost => "Ghost",
+            Self::Magnet => "Magnet",
+            Self::Shield => "Shield",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        Color::from(match self {
+            Self::Ghost => tailwind::SLATE_100,
+            Self::Magnet => tailwind::ROSE_500,
+            Self::Shield => tailwind::SKY_400,
+        })
+    }
+
+    fn mesh(&self) -> Mesh {
+        match self {
+            Self::Ghost => Sphere::new(0.4).into(),
+            Self::Magnet => Torus::new(0.15, 0.4).into(),
+            Self::Shield => Cylinder::new(0.45, 0.1).into(),
+        }
+    }
+}
+
+#[derive(Component)]
+#[require(GameEntity)]
+pub struct PowerUp(pub PowerUpKind);
+
+// the level tick the power-up disappears on if it hasn't been collected
+#[derive(Component)]
+struct PowerUpExpiry(u64);
+
+// the power-ups active on a snake, with the moves each one has left
+#[derive(Component, Default, Clone, Debug)]
+pub struct SnakeEffects(HashMap<PowerUpKind, u32>);
+
+impl SnakeEffects {
+    pub fn remaining(&self, kind: PowerUpKind) -> u32 {
+        self.0.get(&kind).copied().unwrap_or_default()
+    }
+
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.remaining(kind) > 0
+    }
+
+    // collecting a power-up that is already active tops it back up
+    pub fn grant(&mut self, kind: PowerUpKind, moves: u32) {
+        let remaining = self.0.entry(kind).or_default();
+        *remaining = (*remaining).max(moves);
+    }
+
+    pub fn consume(&mut self, kind: PowerUpKind) {
+        self.0.remove(&kind);
+    }
+
+    // called once per move
+    pub fn advance(&mut self) {
+        for remaining in self.0.values_mut() {
+            *remaining = remaining.saturating_sub(1);
+        }
+        self.0.retain(|_, remaining| *remaining > 0);
+    }
+}
+
+fn on_add_power_up(
+    trigger: Trigger<OnAdd, PowerUp>,
+    query: Query<&PowerUp>,
+    assets: Res<PowerUpAssets>,
+    mut commands: Commands,
+) {
+    let kind = query.get(trigger.entity()).unwrap().0;
+    let (mesh, material) = &assets.0[&kind];
+    commands
+        .entity(trigger.entity())
+        .insert((Mesh3d(mesh.clone()), MeshMaterial3d(material.clone())));
+}
+
+fn insert_power_up_assets(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    commands.insert_resource(PowerUpAssets(HashMap::from_iter(PowerUpKind::ALL.map(
+        |kind| {
+            let material = StandardMaterial {
+                base_color: kind.color(),
+                emissive: LinearRgba::from(kind.color()),
+                perceptual_roughness: 1.0,
+                ..default()
+            };
+            (kind, (meshes.add(kind.mesh()), materials.add(material)))
+        },
+    ))));
+}
+
+fn expire_power_ups(
+    power_up_query: Query<(Entity, &PowerUpExpiry)>,
+    tick_query: Query<&LevelTick>,
+    mut commands: Commands,
+) {
+    let Ok(tick) = tick_query.get_single() else {
+        return;
+    };
+
+    for (entity, expiry) in power_up_query.iter() {
+        if tick.0 >= expiry.0 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn collect_power_ups(
+    mut snake_query: Query<(&GridPosition, &mut SnakeEffects), Changed<GridPosition>>,
+    power_up_query: Query<(Entity, &PowerUp, &GridPosition), Without<SnakeHead>>,
+    rules: Res<GameRules>,
+    mut commands: Commands,
+) {
+    for (snake_grid_position, mut effects) in snake_query.iter_mut() {
+        for (entity, power_up, _) in power_up_query
+            .iter()
+            .filter(|(.., gp)| *gp == snake_grid_position)
+        {
+            commands.entity(entity).despawn_recursive();
+            effects.grant(power_up.0, rules.power_up(power_up.0).moves);
+        }
+    }
+}
+
+fn spawn_power_ups(
+    arena_query: Query<&ArenaSize>,
+    power_up_query: Query<&PowerUp>,
+    snake_query: Query<&SnakeHead>,
+    tick_query: Query<&LevelTick>,
+    occupancy: Res<Occupancy>,
+    rules: Res<GameRules>,
+    mut rng: ResMut<GameRng>,
+    mut commands: Commands,
+) {
+    let (Ok(tick), Some(arena_size)) = (tick_query.get_single(), arena_query.iter().next()) else {
+        return;
+    };
+
+    // at most one power-up is on the field, appearing at a fixed interval
+    if tick.0 == 0
+        || tick.0 % rules.power_up_interval.max(1) != 0
+        || !power_up_query.is_empty()
+        || snake_query.is_empty()
+    {
+        return;
+    }
+
+    let pool: Vec<_> = arena_size
+        .cells()
+        .filter(|cell| occupancy.is_empty(*cell))
+        .map(GridPosition)
+        .collect();
+    if pool.is_empty() {
+        return;
+    }
+
+    let weights = PowerUpKind::ALL.map(|kind| rules.power_up(kind).weight);
+    let Ok(distribution) = WeightedIndex::new(weights) else {
+        return;
+    };
+    let kind = PowerUpKind::ALL[distribution.sample(&mut *rng)];
+    let grid_position = pool[rng.gen_range(0..pool.len())];
+    commands.spawn((
+        PowerUp(kind),
+        grid_position,
+        PowerUpExpiry(tick.0 + rules.power_up_lifetime),
+    ));
+}
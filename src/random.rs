@@ -0,0 +1,106 @@
+use std::env;
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, thread_rng, Error, Rng, RngCore, SeedableRng};
+
+use crate::game::SpawnLevel;
+
+pub struct RandomPlugin;
+
+impl Plugin for RandomPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SeedSource>()
+            .init_resource::<GameRng>()
+            .add_observer(on_spawn_level);
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug)]
+pub enum SeedSource {
+    // every level is seeded with the same value
+    Fixed(u64),
+    // every level is seeded with a fresh value from the OS
+    Random,
+}
+
+impl Default for SeedSource {
+    fn default() -> Self {
+        seed_from_args()
+            .or_else(seed_from_env)
+            .map_or(Self::Random, Self::Fixed)
+    }
+}
+
+impl SeedSource {
+    fn next_seed(&self) -> u64 {
+        match self {
+            Self::Fixed(seed) => *seed,
+            Self::Random => thread_rng().gen(),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::from_seed(0)
+    }
+}
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+fn seed_from_args() -> Option<u64> {
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if let Some(value) = arg.strip_prefix("--seed=") {
+            return value.parse().ok();
+        }
+        if arg == "--seed" {
+            return args.next()?.parse().ok();
+        }
+    }
+    None
+}
+
+fn seed_from_env() -> Option<u64> {
+    env::var("SNAKE_SEED").ok()?.parse().ok()
+}
+
+fn on_spawn_level(_: Trigger<SpawnLevel>, source: Res<SeedSource>, mut rng: ResMut<GameRng>) {
+    // reseed at the start of every level so a seed always reproduces the same game
+    *rng = GameRng::from_seed(source.next_seed());
+}
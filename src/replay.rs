@@ -0,0 +1,260 @@
+use std::{
+    collections::VecDeque,
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bot::{BotKind, GridView, Opponent, SnakeController},
+    game::{GameMode, GameState, SpawnLevel},
+    level::LevelTick,
+    random::{GameRng, SeedSource},
+    snake::{Player, SnakeCollided, TurnSnake},
+};
+
+const REPLAY_DIRECTORY: &str = "replays";
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().contains_resource::<ReplayMode>() {
+            app.insert_resource(ReplayMode::from_args());
+        }
+
+        // a replay only reproduces the run when the rng is seeded identically and the mode and
+        // opponent match
+        if let ReplayMode::Play(replay) = app.world().resource::<ReplayMode>() {
+            let (seed, mode, opponent) = (replay.seed, replay.mode, replay.opponent);
+            app.insert_resource(SeedSource::Fixed(seed))
+                .insert_resource(mode)
+                .insert_resource(Opponent(opponent));
+        }
+
+        app.init_resource::<ReplayRecording>()
+            .add_observer(on_spawn_level)
+            .add_observer(on_turn_snake)
+            .add_observer(on_snake_collided);
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    #[serde(default)]
+    pub mode: GameMode,
+    // bots aren't recorded, they make the same moves again on playback
+    #[serde(default)]
+    pub opponent: Option<BotKind>,
+    pub turns: Vec<ReplayTurn>,
+}
+
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        ron::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let contents =
+            ron::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, contents)
+    }
+}
+
+// a direction change applied at the start of the given level tick
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct ReplayTurn {
+    pub tick: u64,
+    pub direction: Dir3,
+    #[serde(default)]
+    pub player: Player,
+}
+
+// makes one player's recorded turns again, each on the tick it was recorded on
+pub struct ReplayController {
+    turns: VecDeque<ReplayTurn>,
+}
+
+impl ReplayController {
+    pub fn new(replay: &Replay, player: Player) -> Self {
+        Self {
+            turns: replay
+                .turns
+                .iter()
+                .filter(|turn| turn.player == player)
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+impl SnakeController for ReplayController {
+    fn next_direction(&mut self, view: &GridView) -> Option<Dir3> {
+        // turns from ticks that have already passed can't be made any more
+        while self.turns.front().is_some_and(|turn| turn.tick < view.tick) {
+            self.turns.pop_front();
+        }
+        if self.turns.front()?.tick != view.tick {
+            return None;
+        }
+        self.turns.pop_front().map(|turn| turn.direction)
+    }
+
+    fn is_player(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Resource)]
+pub enum ReplayMode {
+    // inputs come from the player and are saved when the snake collides
+    Record,
+    // inputs come from the replay and nothing is saved
+    Play(Replay),
+    // inputs come from elsewhere, such as a training agent, and nothing is saved
+    Off,
+}
+
+impl ReplayMode {
+    fn from_args() -> Self {
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg != "--replay" {
+                continue;
+            }
+            let Some(path) = args.next() else {
+                break;
+            };
+            // a replay that can't be read starts a normal game instead
+            match Replay::load(&path) {
+                Ok(replay) => return Self::Play(replay),
+                Err(e) => {
+                    error!("failed to load replay {path}, recording a new game instead: {e}");
+                    break;
+                }
+            }
+        }
+        Self::Record
+    }
+}
+
+#[derive(Resource, Default)]
+struct ReplayRecording {
+    turns: Vec<ReplayTurn>,
+    // both snakes can crash on the same move in versus, but the game is only saved once
+    saved: bool,
+}
+
+fn on_spawn_level(_: Trigger<SpawnLevel>, mut recording: ResMut<ReplayRecording>) {
+    *recording = ReplayRecording::default();
+}
+
+fn on_turn_snake(
+    trigger: Trigger<TurnSnake>,
+    tick_query: Query<&LevelTick>,
+    player_query: Query<&Player>,
+    mode: Res<ReplayMode>,
+    mut recording: ResMut<ReplayRecording>,
+) {
+    let (ReplayMode::Record, Ok(tick)) = (&*mode, tick_query.get_single()) else {
+        return;
+    };
+
+    recording.turns.push(ReplayTurn {
+        tick: tick.0,
+        direction: trigger.event().0,
+        player: player_query
+            .get(trigger.entity())
+            .copied()
+            .unwrap_or_default(),
+    });
+}
+
+fn on_snake_collided(
+    _: Trigger<SnakeCollided>,
+    mode: Res<ReplayMode>,
+    game_mode: Res<GameMode>,
+    opponent: Res<Opponent>,
+    state: Res<State<GameState>>,
+    mut recording: ResMut<ReplayRecording>,
+    rng: Res<GameRng>,
+) {
+    // the title screen demo isn't worth keeping
+    if !matches!(*mode, ReplayMode::Record) || recording.saved || *state != GameState::Play {
+        return;
+    }
+
+    recording.saved = true;
+    let replay = Replay {
+        seed: rng.seed(),
+        mode: *game_mode,
+        opponent: opponent.0,
+        turns: recording.turns.clone(),
+    };
+    match save_replay(&replay) {
+        Ok(path) => info!("saved replay to {}", path.display()),
+        Err(e) => error!("failed to save replay: {e}"),
+    }
+}
+
+fn save_replay(replay: &Replay) -> io::Result<PathBuf> {
+    fs::create_dir_all(REPLAY_DIRECTORY)?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = Path::new(REPLAY_DIRECTORY).join(format!("{timestamp}-{}.ron", replay.seed));
+    replay.save(&path)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{arena::ArenaSize, occupancy::Occupancy};
+
+    #[test]
+    fn replay_controller_turns_on_the_recorded_ticks() {
+        let turn = |tick, direction, player| ReplayTurn {
+            tick,
+            direction,
+            player,
+        };
+        let replay = Replay {
+            turns: vec![
+                turn(2, Dir3::X, Player::One),
+                turn(2, Dir3::NEG_X, Player::Two),
+                turn(4, Dir3::Z, Player::One),
+                turn(4, Dir3::NEG_X, Player::One),
+            ],
+            ..default()
+        };
+        let mut controller = ReplayController::new(&replay, Player::One);
+
+        let arena = ArenaSize::new(11, 11);
+        let occupancy = Occupancy::default();
+        let mut directions = Vec::new();
+        for tick in 0..6 {
+            let view = GridView {
+                tick,
+                actions: None,
+                head: IVec3::ZERO,
+                direction: Dir3::NEG_Z,
+                arena: &arena,
+                wraps: false,
+                occupancy: &occupancy,
+                food: &[],
+            };
+            while let Some(direction) = controller.next_direction(&view) {
+                directions.push((tick, direction));
+            }
+        }
+
+        assert_eq!(directions, [(2, Dir3::X), (4, Dir3::Z), (4, Dir3::NEG_X)]);
+    }
+}
@@ -0,0 +1,387 @@
+use std::{fs, io, path::PathBuf, time::SystemTime};
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{food::FoodKind, game::GameMode, power_up::PowerUpKind};
+
+const RULES_PATH: &str = "assets/rules.ron";
+
+pub struct RulesPlugin;
+
+impl Plugin for RulesPlugin {
+    fn build(&self, app: &mut App) {
+        if app.world().contains_resource::<GameRules>() {
+            return;
+        }
+
+        let mut file = RulesFile {
+            path: PathBuf::from(RULES_PATH),
+            modified: None,
+        };
+        app.insert_resource(file.load());
+
+        // designers can tweak the file while a dev build is running
+        #[cfg(debug_assertions)]
+        {
+            use bevy::time::common_conditions::on_timer;
+            use std::time::Duration;
+
+            app.insert_resource(file).add_systems(
+                Update,
+                reload_rules.run_if(on_timer(Duration::from_secs(1))),
+            );
+        }
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GameRules {
+    // size of the rectangular arena when a level starts
+    pub arena_width: i32,
+    pub arena_height: i32,
+    // how much the width and height grow each time the arena expands
+    pub arena_growth: i32,
+    // the arena expands once the score reaches its area divided by this
+    pub expansion_divisor: u32,
+    // seconds between each snake move
+    pub move_interval: f32,
+    // body segments the snake grows into after spawning
+    pub initial_body_length: usize,
+    // whether a snake may move into the cell its tail is leaving
+    pub tail_collision: TailCollision,
+    // chance of a special food appearing alongside each normal food
+    pub special_food_chance: f64,
+    // what each kind of food is worth and how often it appears
+    pub foods: HashMap<FoodKind, FoodRules>,
+    // ticks between power-ups appearing, while none is on the field
+    pub power_up_interval: u64,
+    // ticks before an uncollected power-up disappears
+    pub power_up_lifetime: u64,
+    pub power_ups: HashMap<PowerUpKind, PowerUpRules>,
+    // how many cells away the magnet pulls food from
+    pub magnet_radius: i32,
+    // how the snake speeds up in each game mode
+    pub speed_curves: HashMap<GameMode, SpeedCurve>,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            arena_width: 11,
+            arena_height: 11,
+            arena_growth: 2,
+            expansion_divisor: 4,
+            move_interval: 0.3,
+            initial_body_length: 2,
+            tail_collision: TailCollision::Classic,
+            special_food_chance: 0.25,
+            foods: HashMap::from_iter(FoodKind::ALL.map(|kind| (kind, FoodRules::new(kind)))),
+            power_up_interval: 640,
+            power_up_lifetime: 480,
+            power_ups: HashMap::from_iter(
+                PowerUpKind::ALL.map(|kind| (kind, PowerUpRules::new(kind))),
+            ),
+            magnet_radius: 2,
+            speed_curves: HashMap::from_iter(
+                GameMode::ALL.map(|mode| (mode, SpeedCurve::default())),
+            ),
+        }
+    }
+}
+
+impl GameRules {
+    pub fn speed_curve(&self, mode: GameMode) -> SpeedCurve {
+        self.speed_curves.get(&mode).copied().unwrap_or_default()
+    }
+
+    pub fn food(&self, kind: FoodKind) -> FoodRules {
+        self.foods
+            .get(&kind)
+            .copied()
+            .unwrap_or_else(|| FoodRules::new(kind))
+    }
+
+    pub fn power_up(&self, kind: PowerUpKind) -> PowerUpRules {
+        self.power_ups
+            .get(&kind)
+            .copied()
+            .unwrap_or_else(|| PowerUpRules::new(kind))
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let rules: Self =
+            ron::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        rules
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(rules)
+    }
+
+    // catches values the game can't run with, which would otherwise panic mid-game after a reload
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = |value: f32| value.is_finite() && value > 0.0;
+        if self.expansion_divisor == 0 {
+            return Err("expansion_divisor must be above zero".to_string());
+        }
+        if !positive(self.move_interval) {
+            return Err("move_interval must be a positive number of seconds".to_string());
+        }
+        for (mode, curve) in self.speed_curves.iter() {
+            if !positive(curve.factor)
+                || !curve.min_interval.is_finite()
+                || curve.min_interval < 0.0
+            {
+                return Err(format!(
+                    "the {} speed curve needs a positive factor and a min_interval of at least zero",
+                    mode.name()
+                ));
+            }
+        }
+        for (kind, food) in self.foods.iter() {
+            if !positive(food.speed_factor) {
+                return Err(format!(
+                    "{} food needs a positive speed_factor",
+                    kind.name()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TailCollision {
+    // a tail blocks its cell until the move is over
+    Strict,
+    // a tail leaves its cell as the head arrives, unless the snake is growing
+    Classic,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpeedDriver {
+    Score,
+    Length,
+    Seconds,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct SpeedCurve {
+    pub driver: SpeedDriver,
+    // how much the driver has to increase to gain a speed level
+    pub step: u32,
+    // each speed level multiplies the move interval by this
+    pub factor: f32,
+    // the move interval never drops below this many seconds
+    pub min_interval: f32,
+}
+
+impl Default for SpeedCurve {
+    fn default() -> Self {
+        Self {
+            driver: SpeedDriver::Score,
+            step: 5,
+            factor: 0.9,
+            min_interval: 0.1,
+        }
+    }
+}
+
+impl SpeedCurve {
+    pub fn level(&self, value: u32) -> u32 {
+        value / self.step.max(1)
+    }
+
+    pub fn interval(&self, base_interval: f32, level: u32) -> f32 {
+        (base_interval * self.factor.powi(level as i32)).max(self.min_interval.min(base_interval))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct FoodRules {
+    // relative chance of a special food being this kind, normal food ignores it
+    pub weight: u32,
+    pub score: u32,
+    // segments gained, negative values remove segments from the tail
+    pub growth: i32,
+    // multiplies the move interval while the effect lasts
+    pub speed_factor: f32,
+    pub effect_ticks: u64,
+    // ticks before the food disappears uneaten, it stays forever when none
+    pub lifetime: Option<u64>,
+}
+
+impl Default for FoodRules {
+    fn default() -> Self {
+        Self {
+            weight: 0,
+            score: 1,
+            growth: 1,
+            speed_factor: 1.0,
+            effect_ticks: 0,
+            lifetime: None,
+        }
+    }
+}
+
+impl FoodRules {
+    fn new(kind: FoodKind) -> Self {
+        let default = Self::default();
+        match kind {
+            FoodKind::Normal => default,
+            FoodKind::Golden => Self {
+                weight: 2,
+                score: 5,
+                ..default
+            },
+            FoodKind::Shrink => Self {
+                weight: 2,
+                score: 0,
+                growth: -2,
+                ..default
+            },
+            FoodKind::Speed => Self {
+                weight: 1,
+                speed_factor: 0.7,
+                effect_ticks: 320,
+                ..default
+            },
+            FoodKind::Slow => Self {
+                weight: 1,
+                speed_factor: 1.5,
+                effect_ticks: 320,
+                ..default
+            },
+            FoodKind::Bonus => Self {
+                weight: 2,
+                score: 3,
+                lifetime: Some(320),
+                ..default
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct PowerUpRules {
+    // relative chance of a power-up being this kind
+    pub weight: u32,
+    // how many moves the effect lasts once collected
+    pub moves: u32,
+}
+
+impl Default for PowerUpRules {
+    fn default() -> Self {
+        Self {
+            weight: 1,
+            moves: 30,
+        }
+    }
+}
+
+impl PowerUpRules {
+    fn new(kind: PowerUpKind) -> Self {
+        let default = Self::default();
+        match kind {
+            PowerUpKind::Ghost => Self {
+                moves: 20,
+                ..default
+            },
+            PowerUpKind::Magnet => default,
+            PowerUpKind::Shield => Self {
+                moves: 60,
+                ..default
+            },
+        }
+    }
+}
+
+#[derive(Resource)]
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+struct RulesFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl RulesFile {
+    fn load(&mut self) -> GameRules {
+        self.modified = self.modified_time();
+        let path = self.path.to_string_lossy();
+        match GameRules::load(&path) {
+            Ok(rules) => rules,
+            Err(e) => {
+                warn!("failed to load {path}, using default rules: {e}");
+                GameRules::default()
+            }
+        }
+    }
+
+    fn modified_time(&self) -> Option<SystemTime> {
+        fs::metadata(&self.path).and_then(|m| m.modified()).ok()
+    }
+}
+
+#[cfg(debug_assertions)]
+fn reload_rules(mut file: ResMut<RulesFile>, mut rules: ResMut<GameRules>) {
+    if file.modified_time() == file.modified {
+        return;
+    }
+
+    info!("reloading {}", file.path.display());
+    *rules = file.load();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_rules_are_valid() {
+        assert_eq!(GameRules::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn zero_expansion_divisor_is_rejected() {
+        let rules = GameRules {
+            expansion_divisor: 0,
+            ..default()
+        };
+        assert!(rules.validate().is_err());
+    }
+
+    #[test]
+    fn bad_intervals_are_rejected() {
+        for move_interval in [0.0, -0.3, f32::NAN, f32::INFINITY] {
+            let rules = GameRules {
+                move_interval,
+                ..default()
+            };
+            assert!(rules.validate().is_err(), "{move_interval}");
+        }
+
+        let mut rules = GameRules::default();
+        rules.speed_curves.insert(
+            GameMode::Classic,
+            SpeedCurve {
+                factor: -0.9,
+                ..default()
+            },
+        );
+        assert!(rules.validate().is_err());
+
+        let mut rules = GameRules::default();
+        rules.speed_curves.insert(
+            GameMode::Wrap,
+            SpeedCurve {
+                min_interval: f32::NAN,
+                ..default()
+            },
+        );
+        assert!(rules.validate().is_err());
+    }
+}
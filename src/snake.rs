@@ -1,37 +1,95 @@
-use std::f32::consts::PI;
+use std::{collections::VecDeque, f32::consts::PI, time::Duration};
 
-use bevy::prelude::*;
+use bevy::{prelude::*, scene::SceneInstanceReady, utils::HashMap};
 use bevy_asset_loader::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    action::SnakeAction,
     arena::{ArenaSet, ArenaSize},
-    game::{AppExt, GameEntity, SpawnLevel},
+    game::{AppExt, GameEntity, GameMode, SpawnLevel},
     grid::{GridPosition, GridSet},
+    level::{LevelTick, Score},
+    occupancy::{Occupancy, Occupant},
+    power_up::{PowerUpKind, SnakeEffects},
+    rules::{GameRules, SpeedDriver, TailCollision},
+    stats::SnakeStats,
 };
 
 pub struct SnakePlugin;
 
 impl Plugin for SnakePlugin {
     fn build(&self, app: &mut App) {
-        app.add_game_assets::<SnakeAssets>()
-            .configure_sets(FixedUpdate, SnakeSet.after(ArenaSet).before(GridSet))
+        app.configure_sets(FixedUpdate, SnakeSet.after(ArenaSet).before(GridSet))
             .add_observer(on_spawn_level)
+            .add_observer(on_turn_snake)
+            .add_observer(on_shrink_snake)
+            .add_observer(on_remove_snake_body_segment)
+            .add_systems(
+                FixedUpdate,
+                (update_snake_speed, move_snake).chain().in_set(SnakeSet),
+            );
+    }
+}
+
+pub struct SnakeVisualPlugin;
+
+impl Plugin for SnakeVisualPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_game_assets::<SnakeAssets>()
+            .init_resource::<TintedMaterials>()
             .add_observer(on_add_snake_head)
-            .add_systems(Update, control_snake)
+            .add_observer(on_add_snake_body_segment)
+            .add_observer(tint_snake_scene)
             .add_systems(
                 FixedUpdate,
-                (move_snake, (visualise_snake_head, visualise_snake_body))
-                    .chain()
+                (visualise_snake_head, visualise_snake_body)
+                    .after(move_snake)
                     .in_set(SnakeSet),
-            );
+            )
+            .add_systems(Update, interpolate_snake);
     }
 }
 
 #[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
 pub struct SnakeSet;
 
-#[derive(Event)]
-pub struct SnakeCollided;
+// triggered on the head of a snake that crashed
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SnakeCollided {
+    pub cause: CollisionCause,
+    // the cell the head crashed into, which lies outside the arena for walls
+    pub cell: IVec3,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CollisionCause {
+    Wall,
+    OwnBody,
+    // another snake's head or body
+    OtherSnake,
+    Obstacle,
+}
+
+impl CollisionCause {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Wall => "hit the wall",
+            Self::OwnBody => "ran into itself",
+            Self::OtherSnake => "ran into the other snake",
+            Self::Obstacle => "hit an obstacle",
+        }
+    }
+}
+
+// triggered on a snake head to request a change of direction, by its controller for player turns
+// or directly by a training agent
+#[derive(Event, Clone, Copy)]
+pub struct TurnSnake(pub Dir3);
+
+// triggered on a snake head to remove segments from its tail
+#[derive(Event, Clone, Copy)]
+pub struct ShrinkSnake(pub usize);
 
 #[derive(Resource, AssetCollection)]
 struct SnakeAssets {
@@ -45,36 +103,172 @@ struct SnakeAssets {
     head: Handle<Scene>,
 }
 
+// who controls a snake, versus mode has one snake for each player
+#[derive(
+    Component,
+    Serialize,
+    Deserialize,
+    Default,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Clone,
+    Copy,
+)]
+pub enum Player {
+    #[default]
+    One,
+    Two,
+}
+
+impl Player {
+    pub const ALL: [Self; 2] = [Self::One, Self::Two];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::One => "Player 1",
+            Self::Two => "Player 2",
+        }
+    }
+
+    // in order of priority when several are pressed at once
+    pub fn turn_actions(&self) -> [(SnakeAction, Dir3); 4] {
+        match self {
+            Self::One => [
+                (SnakeAction::TurnLeft, Dir3::NEG_X),
+                (SnakeAction::TurnRight, Dir3::X),
+                (SnakeAction::TurnUp, Dir3::NEG_Z),
+                (SnakeAction::TurnDown, Dir3::Z),
+            ],
+            Self::Two => [
+                (SnakeAction::PlayerTwoLeft, Dir3::NEG_X),
+                (SnakeAction::PlayerTwoRight, Dir3::X),
+                (SnakeAction::PlayerTwoUp, Dir3::NEG_Z),
+                (SnakeAction::PlayerTwoDown, Dir3::Z),
+            ],
+        }
+    }
+}
+
 #[derive(Component)]
 #[require(
     GameEntity,
-    SnakeVisual,
+    Player,
+    Score,
     SnakeMoveTimer,
+    SnakeEarlyMove,
+    SnakeSpeed,
     SnakeDirection,
+    SnakeTurnQueue,
+    SnakeBody,
     SnakeBodyBuffer,
-    GridPosition
+    SnakeEffects,
+    SnakeStats,
+    GridPosition,
+    PreviousGridPosition
 )]
 pub struct SnakeHead;
 
 #[derive(Component)]
-#[require(GameEntity, SnakeVisual)]
-struct SnakeBodySegment;
+#[require(GameEntity, PreviousGridPosition)]
+pub struct SnakeBodySegment {
+    // the head this segment follows
+    pub head: Entity,
+}
+
+// marks a snake head that crashed and has stopped moving
+#[derive(Component)]
+pub struct SnakeCrashed;
 
 #[derive(Component, Default)]
-#[require(SceneRoot)]
+#[require(SceneRoot, SnakeRotation)]
 struct SnakeVisual;
 
+// the cell a snake part is moving out of during the current move, and the one it moved out of
+// during the move before
+#[derive(Component, Default)]
+struct PreviousGridPosition {
+    cell: IVec3,
+    before: IVec3,
+}
+
+impl PreviousGridPosition {
+    fn new(cell: IVec3) -> Self {
+        Self { cell, before: cell }
+    }
+
+    fn leave(&mut self, cell: IVec3) {
+        self.before = self.cell;
+        self.cell = cell;
+    }
+}
+
+// player two's copies of the snake materials, keyed by the original
+#[derive(Resource, Default)]
+struct TintedMaterials(HashMap<AssetId<StandardMaterial>, Handle<StandardMaterial>>);
+
+// the rotation a snake part turns through during the current move
+#[derive(Component, Default)]
+struct SnakeRotation {
+    from: Quat,
+    to: Quat,
+}
+
+impl SnakeRotation {
+    fn turn_to(&mut self, rotation: Quat) {
+        self.from = self.to;
+        self.to = rotation;
+    }
+}
+
 #[derive(Component)]
 struct SnakeMoveTimer(Timer);
 
 impl Default for SnakeMoveTimer {
     fn default() -> Self {
-        Self(Timer::from_seconds(0.3, TimerMode::Repeating))
+        Self::new(GameRules::default().move_interval)
     }
 }
 
+impl SnakeMoveTimer {
+    fn new(seconds: f32) -> Self {
+        Self(Timer::from_seconds(seconds, TimerMode::Repeating))
+    }
+
+    // how far through the current move the snake is, the timer only advances on fixed updates so
+    // the time since the last one is added on
+    fn progress(&self, overstep: Duration) -> f32 {
+        let elapsed = self.0.elapsed() + overstep;
+        (elapsed.as_secs_f32() / self.0.duration().as_secs_f32()).min(1.0)
+    }
+}
+
+// a turn moves the snake on the next fixed update rather than when its timer runs out, the move it
+// cuts short is finished off during the next one so the snake doesn't jump ahead
+#[derive(Component, Default)]
+struct SnakeEarlyMove {
+    // the progress through the move when the turn was made, until the snake actually moves
+    pending: Option<f32>,
+    // the part of the previous move that was cut short
+    skipped: f32,
+}
+
+// the current speed level, starting from zero
+#[derive(Component, Default)]
+pub struct SnakeSpeed(pub u32);
+
+// scales the move interval until the given level tick
+#[derive(Component, Clone, Copy)]
+pub struct SnakeSpeedEffect {
+    pub factor: f32,
+    pub until: u64,
+}
+
 #[derive(Component)]
-struct SnakeDirection(Dir3);
+pub struct SnakeDirection(pub Dir3);
 
 impl Default for SnakeDirection {
     fn default() -> Self {
@@ -82,85 +276,235 @@ impl Default for SnakeDirection {
     }
 }
 
+// turns waiting to be applied, one per move
+#[derive(Component, Default)]
+pub struct SnakeTurnQueue(VecDeque<Dir3>);
+
+impl SnakeTurnQueue {
+    const CAPACITY: usize = 3;
+
+    // queues a turn for a snake heading in the given direction, returning whether it was accepted
+    pub fn push(&mut self, direction: Dir3, input_direction: Dir3) -> bool {
+        // validate against the direction the snake will have once the queue is consumed
+        let last_direction = self.0.back().copied().unwrap_or(direction);
+
+        // don't do anything if trying to 180 the snake, or not turning at all
+        if input_direction == -last_direction
+            || input_direction == last_direction
+            || self.0.len() >= Self::CAPACITY
+        {
+            return false;
+        }
+
+        self.0.push_back(input_direction);
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+// the segments a head owns, from the one behind the head to the tail
+#[derive(Component, Default)]
+pub struct SnakeBody(VecDeque<Entity>);
+
+impl SnakeBody {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = Entity> + '_ {
+        self.0.iter().copied()
+    }
+}
+
 #[derive(Component)]
 pub struct SnakeBodyBuffer(pub usize);
 
-#[derive(Component, PartialEq, Eq, PartialOrd, Ord)]
-struct SnakeBodyIndex(u32);
-
 impl Default for SnakeBodyBuffer {
     fn default() -> Self {
-        Self(2)
+        Self(GameRules::default().initial_body_length)
     }
 }
 
-fn on_spawn_level(_: Trigger<SpawnLevel>, mut commands: Commands) {
-    commands.spawn(SnakeHead);
+fn on_spawn_level(
+    _: Trigger<SpawnLevel>,
+    rules: Res<GameRules>,
+    mode: Res<GameMode>,
+    mut commands: Commands,
+) {
+    // a lone snake starts in the centre, versus snakes start side by side
+    let players = &Player::ALL[..mode.players()];
+    let spacing = rules.arena_width / 2;
+    for (i, player) in players.iter().enumerate() {
+        let offset = spacing * (2 * i as i32 + 1 - players.len() as i32) / 2;
+        let position = IVec3::new(offset, 0, 0);
+        commands.spawn((
+            SnakeHead,
+            *player,
+            GridPosition(position),
+            PreviousGridPosition::new(position),
+            SnakeMoveTimer::new(rules.move_interval),
+            SnakeBodyBuffer(rules.initial_body_length),
+        ));
+    }
 }
 
 fn on_add_snake_head(
     trigger: Trigger<OnAdd, SnakeHead>,
-    mut query: Query<&mut SceneRoot>,
     assets: Res<SnakeAssets>,
+    mut commands: Commands,
+) {
+    commands
+        .entity(trigger.entity())
+        .insert((SnakeVisual, SceneRoot(assets.head.clone())));
+}
+
+fn on_add_snake_body_segment(trigger: Trigger<OnAdd, SnakeBodySegment>, mut commands: Commands) {
+    commands.entity(trigger.entity()).insert(SnakeVisual);
+}
+
+fn on_turn_snake(
+    trigger: Trigger<TurnSnake>,
+    mut query: Query<(
+        &SnakeDirection,
+        &mut SnakeTurnQueue,
+        &mut SnakeMoveTimer,
+        &mut SnakeEarlyMove,
+    )>,
+    time: Res<Time<Fixed>>,
+) {
+    let Ok((direction, mut queue, mut timer, mut early)) = query.get_mut(trigger.entity()) else {
+        return;
+    };
+    if !queue.push(direction.0, trigger.event().0) {
+        return;
+    }
+
+    // immediately move - this give more natural input feel
+    if queue.0.len() == 1 {
+        early.pending = Some(timer.progress(time.overstep()));
+        let duration = timer.0.duration();
+        timer.0.set_elapsed(duration);
+    }
+}
+
+fn on_shrink_snake(
+    trigger: Trigger<ShrinkSnake>,
+    mut head_query: Query<(&mut SnakeBody, &mut SnakeBodyBuffer)>,
+    mut commands: Commands,
 ) {
-    query.get_mut(trigger.entity()).unwrap().0 = assets.head.clone();
+    let Ok((mut body, mut buffer)) = head_query.get_mut(trigger.entity()) else {
+        return;
+    };
+
+    // growth that hasn't happened yet is cancelled first
+    let cancelled = trigger.event().0.min(buffer.0);
+    buffer.0 -= cancelled;
+
+    // then segments come off the tail, always leaving one behind the head
+    let remaining = trigger.event().0 - cancelled;
+    let removable = body.len().saturating_sub(1);
+    for _ in 0..remaining.min(removable) {
+        if let Some(entity) = body.0.pop_back() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
 }
 
-fn control_snake(
-    mut query: Query<(&mut SnakeDirection, &mut SnakeMoveTimer)>,
-    input: Res<ButtonInput<KeyCode>>,
+// keeps the head's list in step with segments despawned any other way
+fn on_remove_snake_body_segment(
+    trigger: Trigger<OnRemove, SnakeBodySegment>,
+    segment_query: Query<&SnakeBodySegment>,
+    mut head_query: Query<&mut SnakeBody>,
 ) {
-    for (mut direction, mut timer) in query.iter_mut() {
-        let mut input_direction = None;
-        if input.just_pressed(KeyCode::KeyA) {
-            input_direction = Some(Dir3::NEG_X);
-        } else if input.just_pressed(KeyCode::KeyD) {
-            input_direction = Some(Dir3::X);
-        } else if input.just_pressed(KeyCode::KeyW) {
-            input_direction = Some(Dir3::NEG_Z);
-        } else if input.just_pressed(KeyCode::KeyS) {
-            input_direction = Some(Dir3::Z);
+    let entity = trigger.entity();
+    let Ok(segment) = segment_query.get(entity) else {
+        return;
+    };
+    if let Ok(mut body) = head_query.get_mut(segment.head) {
+        if body.0.contains(&entity) {
+            body.0.retain(|other| *other != entity);
         }
+    }
+}
 
-        // stop here if no input
-        let Some(input_direction) = input_direction else {
-            continue;
+fn update_snake_speed(
+    mut head_query: Query<(
+        &Score,
+        &mut SnakeSpeed,
+        &mut SnakeMoveTimer,
+        &SnakeBody,
+        Option<&SnakeSpeedEffect>,
+    )>,
+    tick_query: Query<&LevelTick>,
+    rules: Res<GameRules>,
+    mode: Res<GameMode>,
+    time: Res<Time<Fixed>>,
+) {
+    let curve = rules.speed_curve(*mode);
+    let tick = tick_query.iter().map(|tick| tick.0).sum::<u64>();
+
+    for (score, mut speed, mut timer, body, effect) in head_query.iter_mut() {
+        let value = match curve.driver {
+            SpeedDriver::Score => score.0,
+            SpeedDriver::Length => body.len() as u32 + 1,
+            SpeedDriver::Seconds => (tick as f32 * time.timestep().as_secs_f32()) as u32,
         };
+        let level = curve.level(value);
+        let interval = curve.interval(rules.move_interval, level);
 
-        // don't do anything if trying to 180 the snake
-        if input_direction
-            == match direction.0 {
-                Dir3::NEG_X => Dir3::X,
-                Dir3::X => Dir3::NEG_X,
-                Dir3::NEG_Z => Dir3::Z,
-                Dir3::Z => Dir3::NEG_Z,
-                _ => continue,
-            }
-        {
-            continue;
+        if speed.0 != level {
+            speed.0 = level;
         }
 
-        // set the new direction
-        direction.0 = input_direction;
+        let seconds = match effect {
+            Some(effect) if tick < effect.until => interval * effect.factor,
+            _ => interval,
+        };
+
+        // rules are validated on load, but a bad interval still shouldn't take the game down
+        let Ok(duration) = Duration::try_from_secs_f32(seconds) else {
+            warn_once!("ignoring an invalid move interval of {seconds} seconds");
+            continue;
+        };
+        if timer.0.duration() == duration {
+            continue;
+        }
 
-        // immediately move - this give more natural input feel
-        let duration = timer.0.duration();
-        timer.0.set_elapsed(duration);
+        // keep how far through the current move the snake is, so speeding up doesn't stutter
+        let progress = timer.0.fraction();
+        timer.0.set_duration(duration);
+        timer.0.set_elapsed(duration.mul_f32(progress));
     }
 }
 
 fn move_snake(
     mut head_query: Query<
         (
-            &SnakeDirection,
+            Entity,
+            &mut SnakeDirection,
+            &mut SnakeTurnQueue,
             &mut SnakeMoveTimer,
             &mut GridPosition,
             &mut SnakeBodyBuffer,
+            &mut SnakeEffects,
+            &mut PreviousGridPosition,
+            &mut SnakeEarlyMove,
+            &mut SnakeBody,
         ),
-        Without<SnakeBodyIndex>,
+        Without<SnakeBodySegment>,
     >,
-    mut body_query: Query<(&SnakeBodyIndex, &mut GridPosition)>,
+    mut body_query: Query<(&mut GridPosition, &mut PreviousGridPosition), With<SnakeBodySegment>>,
     arena_query: Query<&ArenaSize>,
+    occupancy: Res<Occupancy>,
+    rules: Res<GameRules>,
+    mode: Res<GameMode>,
     time: Res<Time>,
     mut commands: Commands,
 ) {
@@ -170,158 +514,619 @@ fn move_snake(
 
     let arena_size = arena_query.single();
 
-    for (direction, mut timer, mut grid_position, mut buffer) in head_query.iter_mut() {
+    // every snake due to move this tick decides where it is going before any of them move, so
+    // two heads meeting in the same cell crash together
+    let mut moves = Vec::new();
+    for (
+        entity,
+        mut direction,
+        mut queue,
+        mut timer,
+        grid_position,
+        buffer,
+        mut effects,
+        mut previous,
+        mut early,
+        body,
+    ) in head_query.iter_mut()
+    {
         if !timer.0.tick(time.delta()).just_finished() {
             continue;
         }
+        early.skipped = early.pending.take().map_or(0.0, |reached| 1.0 - reached);
+
+        // only the head and tail slide between cells, and both start the move from where they
+        // are, so a snake that doesn't move stays still
+        previous.leave(grid_position.0);
+        if let Some(tail) = body.0.back() {
+            if let Ok((grid_position, mut previous)) = body_query.get_mut(*tail) {
+                previous.leave(grid_position.0);
+            }
+        }
+
+        // power-ups wear off one move at a time, but still apply to this move
+        let active_effects = effects.clone();
+        effects.advance();
+
+        // apply the next queued turn, as long as it doesn't 180 the snake
+        if let Some(next_direction) = queue.0.pop_front() {
+            if next_direction != -direction.0 {
+                direction.0 = next_direction;
+            }
+        }
 
         // move the head forward by the snake's direction, detecting arena bounds collision
-        let mut prev_position = grid_position.0;
-        let next_position = grid_position.0 + direction.0.as_ivec3();
+        let mut next_position = grid_position.0 + direction.0.as_ivec3();
+        if mode.wraps() {
+            next_position = arena_size.wrap(next_position);
+        }
 
-        // check the next position for a wall or any other snake part
-        if [next_position.x, next_position.z]
-            .iter()
-            .any(|e| e > &arena_size.half_size() || e < &-arena_size.half_size())
-            || body_query.iter().any(|(_, gp)| gp.0 == next_position)
-        {
+        // a growing snake keeps its tail where it is
+        let tail = match rules.tail_collision {
+            TailCollision::Classic if buffer.0 == 0 => body.0.back().copied(),
+            _ => None,
+        };
+        moves.push((entity, next_position, active_effects, tail));
+    }
+
+    // the tails leaving their cells this move, which heads may move into
+    let mut vacated: Vec<_> = moves.iter().filter_map(|(.., tail)| *tail).collect();
+    let (crashed, stopped) = loop {
+        let mut crashed = Vec::new();
+        let mut stopped = Vec::new();
+        for (entity, next_position, active_effects, _) in moves.iter() {
+            // check the next position for a wall, an obstacle, another head or any snake's body, a
+            // ghost passes through its own body
+            let ghost = active_effects.is_active(PowerUpKind::Ghost);
+            let cause = match arena_size.contains(*next_position) {
+                false => Some(CollisionCause::Wall),
+                true => occupancy
+                    .get(*next_position)
+                    .iter()
+                    .find_map(|(other, occupant)| match occupant {
+                        Occupant::SnakeHead => {
+                            (other != entity).then_some(CollisionCause::OtherSnake)
+                        }
+                        Occupant::SnakeBody { .. } if vacated.contains(other) => None,
+                        Occupant::SnakeBody { head } if head == entity => {
+                            (!ghost).then_some(CollisionCause::OwnBody)
+                        }
+                        Occupant::SnakeBody { .. } => Some(CollisionCause::OtherSnake),
+                        Occupant::Obstacle => Some(CollisionCause::Obstacle),
+                        _ => None,
+                    })
+                    .or_else(|| {
+                        moves
+                            .iter()
+                            .any(|(other, other_next, ..)| {
+                                other != entity && other_next == next_position
+                            })
+                            .then_some(CollisionCause::OtherSnake)
+                    }),
+            };
+            let Some(cause) = cause else {
+                continue;
+            };
+
+            // a shield absorbs the collision and the snake stays where it is
+            if active_effects.is_active(PowerUpKind::Shield) {
+                stopped.push(*entity);
+            } else {
+                crashed.push((
+                    *entity,
+                    SnakeCollided {
+                        cause,
+                        cell: *next_position,
+                    },
+                ));
+            }
+        }
+
+        // a stopped snake's tail stays too, which can block a head that was moving into it
+        let count = vacated.len();
+        vacated.retain(|tail| {
+            moves
+                .iter()
+                .all(|(entity, .., other)| *other != Some(*tail) || !stopped.contains(entity))
+        });
+        if vacated.len() == count {
+            break (crashed, stopped);
+        }
+    };
+
+    // any crash ends the game, so every snake stops where it is
+    if !crashed.is_empty() {
+        for (_, _, _, mut timer, ..) in head_query.iter_mut() {
             timer.0.pause();
-            commands.trigger(SnakeCollided);
-            continue;
         }
-        grid_position.0 += direction.0.as_ivec3();
-
-        // shift all body segments forward
-        let mut last_index = 0;
-        for (index, mut grid_position) in body_query.iter_mut().sort::<&SnakeBodyIndex>() {
-            let temp_grid_position = grid_position.0;
-            grid_position.0 = prev_position;
-            prev_position = temp_grid_position;
-            last_index = index.0;
+        for (entity, collision) in crashed {
+            commands.entity(entity).insert(SnakeCrashed);
+            commands.trigger_targets(collision, entity);
         }
+        return;
+    }
 
-        if buffer.0 == 0 {
+    for (entity, next_position, ..) in moves {
+        let Ok((_, _, _, _, mut grid_position, mut buffer, mut effects, _, _, mut body)) =
+            head_query.get_mut(entity)
+        else {
+            continue;
+        };
+        if stopped.contains(&entity) {
+            effects.consume(PowerUpKind::Shield);
             continue;
         }
 
-        buffer.0 -= 1;
+        let prev_position = grid_position.0;
+        grid_position.0 = next_position;
 
-        // spawn the next body segment to fill the last spot
-        commands.spawn((
-            SnakeBodySegment,
-            SnakeBodyIndex(last_index + 1),
-            GridPosition(prev_position),
-        ));
+        // a growing snake fills the cell the head left with a new segment
+        if buffer.0 > 0 {
+            buffer.0 -= 1;
+            let segment = commands
+                .spawn((
+                    SnakeBodySegment { head: entity },
+                    GridPosition(prev_position),
+                    PreviousGridPosition::new(prev_position),
+                ))
+                .id();
+            body.0.push_front(segment);
+            continue;
+        }
+
+        // otherwise the tail segment is moved there, leaving every other segment where it is
+        let Some(tail) = body.0.pop_back() else {
+            continue;
+        };
+        let tail_slide = match body_query.get_mut(tail) {
+            Ok((mut grid_position, mut previous)) => {
+                grid_position.0 = prev_position;
+                std::mem::replace(&mut *previous, PreviousGridPosition::new(prev_position))
+            }
+            Err(_) => continue,
+        };
+        body.0.push_front(tail);
+
+        // the new tail slides into its cell from the old one
+        if let Some(Ok((_, mut previous))) = body.0.back().map(|e| body_query.get_mut(*e)) {
+            *previous = tail_slide;
+        }
     }
 }
 
 fn visualise_snake_head(
-    mut query: Query<(&SnakeDirection, &mut Transform), Changed<GridPosition>>,
+    mut query: Query<(&SnakeDirection, &mut SnakeRotation), Changed<GridPosition>>,
 ) {
-    for (direction, mut transform) in query.iter_mut() {
-        transform.rotation = Quat::from_rotation_y(match direction.0 {
+    for (direction, mut rotation) in query.iter_mut() {
+        rotation.turn_to(Quat::from_rotation_y(match direction.0 {
             Dir3::NEG_Z => 0.0,
             Dir3::Z => PI,
             Dir3::NEG_X => PI * 0.5,
             _ => PI * 1.5,
-        });
+        }));
     }
 }
 
 fn visualise_snake_body(
-    mut body_query: Query<(
-        Entity,
-        Ref<GridPosition>,
-        &SnakeBodyIndex,
-        &mut Transform,
-        &mut SceneRoot,
-    )>,
-    head_query: Query<&GridPosition, With<SnakeHead>>,
+    head_query: Query<(&GridPosition, Ref<SnakeBody>), With<SnakeHead>>,
+    mut body_query: Query<
+        (&GridPosition, &mut SnakeRotation, &mut SceneRoot),
+        With<SnakeBodySegment>,
+    >,
     assets: Option<Res<SnakeAssets>>,
 ) {
-    let (Some(assets), Ok(head_grid_position)) = (assets, head_query.get_single()) else {
+    let Some(assets) = assets else {
         return;
     };
 
-    if body_query.iter().all(|(_, gp, ..)| !gp.is_changed()) {
+    for (head_grid_position, body) in head_query.iter() {
+        if !body.is_changed() {
+            continue;
+        }
+
+        // a move only brings a new segment in behind the head and a new tail, the rest of the
+        // body keeps its shape
+        visualise_segment(&body, 0, head_grid_position, &mut body_query, &assets);
+        if body.len() > 1 {
+            let tail = body.len() - 1;
+            visualise_segment(&body, tail, head_grid_position, &mut body_query, &assets);
+        }
+    }
+}
+
+fn visualise_segment(
+    body: &SnakeBody,
+    i: usize,
+    head_grid_position: &GridPosition,
+    body_query: &mut Query<
+        (&GridPosition, &mut SnakeRotation, &mut SceneRoot),
+        With<SnakeBodySegment>,
+    >,
+    assets: &SnakeAssets,
+) {
+    let Some(&entity) = body.0.get(i) else {
         return;
+    };
+    let Ok((grid_position, ..)) = body_query.get(entity) else {
+        return;
+    };
+    let next_grid_position = match i {
+        0 => head_grid_position,
+        _ => match body_query.get(body.0[i - 1]) {
+            Ok((grid_position, ..)) => grid_position,
+            Err(_) => return,
+        },
+    };
+
+    // determine the direction to the next and previous segement
+    let forward_direction = grid_direction(grid_position, next_grid_position);
+    let tail = i + 1 == body.len();
+    let back_direction = match body.0.get(i + 1).map(|e| body_query.get(*e)) {
+        Some(Ok((prev_grid_position, ..))) => grid_direction(prev_grid_position, grid_position),
+        _ => forward_direction,
+    };
+
+    // determine the rotation and scene to show for the visual
+    let (rotation, scene) = match forward_direction.abs() == back_direction.abs() {
+        true => (
+            match forward_direction {
+                Dir3::NEG_Z => 0.0,
+                Dir3::Z => PI,
+                Dir3::NEG_X => PI * 0.5,
+                _ => PI * 1.5,
+            },
+            match tail {
+                true => assets.body_end.clone(),
+                false => assets.body_straight.clone(),
+            },
+        ),
+        false => (
+            if forward_direction == Dir3::NEG_Z && back_direction == Dir3::X
+                || forward_direction == Dir3::NEG_X && back_direction == Dir3::Z
+            {
+                PI * 1.5
+            } else if forward_direction == Dir3::Z && back_direction == Dir3::NEG_X
+                || forward_direction == Dir3::X && back_direction == Dir3::NEG_Z
+            {
+                PI * 0.5
+            } else if forward_direction == Dir3::NEG_Z && back_direction == Dir3::NEG_X
+                || forward_direction == Dir3::X && back_direction == Dir3::Z
+            {
+                PI
+            } else {
+                0.0
+            },
+            assets.body_corner.clone(),
+        ),
+    };
+
+    // apply the rotation and scene, segments appearing behind the head already face the right way
+    let (_, mut snake_rotation, mut scene_root) = body_query.get_mut(entity).unwrap();
+    snake_rotation.turn_to(Quat::from_rotation_y(rotation));
+    if i == 0 {
+        snake_rotation.from = snake_rotation.to;
+    }
+    if scene_root.0 != scene {
+        scene_root.0 = scene;
     }
+}
 
-    // need to order the entities and look around them to calculate everything we need
-    let ordered_entities: Vec<_> = body_query
-        .iter()
-        .sort::<&SnakeBodyIndex>()
-        .rev()
-        .map(|(e, ..)| e)
-        .collect();
-
-    for i in 0..ordered_entities.len() {
-        let entity = ordered_entities[i];
-        let (_, grid_position, ..) = body_query.get(entity).unwrap();
-        let next_grid_position = match i + 1 < ordered_entities.len() {
-            true => body_query.get(ordered_entities[i + 1]).unwrap().1.clone(),
-            false => *head_grid_position,
+// blends every snake part between its previous and current cell, following its head's move timer
+fn interpolate_snake(
+    head_query: Query<(&SnakeMoveTimer, &SnakeEarlyMove)>,
+    mut query: Query<(
+        Entity,
+        Option<&SnakeBodySegment>,
+        &GridPosition,
+        &PreviousGridPosition,
+        &SnakeRotation,
+        &mut Transform,
+    )>,
+    time: Res<Time<Fixed>>,
+) {
+    for (entity, segment, grid_position, previous_position, rotation, mut transform) in
+        query.iter_mut()
+    {
+        let head = segment.map_or(entity, |segment| segment.head);
+        let Ok((timer, early)) = head_query.get(head) else {
+            continue;
         };
 
-        // determine the direction to the next and previous segement
-        let forward_direction = grid_direction(&grid_position, &next_grid_position);
-        let back_direction = match i {
-            0 => forward_direction,
-            _ => {
-                let prev_grid_position = body_query.get(ordered_entities[i - 1]).unwrap().1;
-                grid_direction(&prev_grid_position, &grid_position)
-            }
-        };
+        // a turn holds the snake where it was until it actually moves
+        let progress = early
+            .pending
+            .unwrap_or_else(|| timer.progress(time.overstep()));
+
+        // a move following an early one starts from where the snake was when it was cut short
+        let to = grid_position.0.as_vec3();
+        let from = to
+            - step(previous_position.cell, grid_position.0)
+            - step(previous_position.before, previous_position.cell) * early.skipped;
+
+        // most of the body sits still, so only write transforms that actually change
+        let translation = from.lerp(to, progress);
+        let rotation = rotation.from.slerp(rotation.to, progress);
+        if transform.translation != translation || transform.rotation != rotation {
+            transform.translation = translation;
+            transform.rotation = rotation;
+        }
+    }
+}
 
-        // determine the rotation and scene to show for the visual
-        let (rotation, scene) = match forward_direction.abs() == back_direction.abs() {
-            true => (
-                match forward_direction {
-                    Dir3::NEG_Z => 0.0,
-                    Dir3::Z => PI,
-                    Dir3::NEG_X => PI * 0.5,
-                    _ => PI * 1.5,
-                },
-                match i {
-                    0 => assets.body_end.clone(),
-                    _ => assets.body_straight.clone(),
-                },
-            ),
-            false => (
-                if forward_direction == Dir3::NEG_Z && back_direction == Dir3::X
-                    || forward_direction == Dir3::NEG_X && back_direction == Dir3::Z
-                {
-                    PI * 1.5
-                } else if forward_direction == Dir3::Z && back_direction == Dir3::NEG_X
-                    || forward_direction == Dir3::X && back_direction == Dir3::NEG_Z
-                {
-                    PI * 0.5
-                } else if forward_direction == Dir3::NEG_Z && back_direction == Dir3::NEG_X
-                    || forward_direction == Dir3::X && back_direction == Dir3::Z
-                {
-                    PI
-                } else {
-                    0.0
-                },
-                assets.body_corner.clone(),
-            ),
+// parts crossing the wrap-around seam slide in from beyond the edge
+fn step(from: IVec3, to: IVec3) -> Vec3 {
+    let delta = to - from;
+    match delta.abs().max_element() > 1 {
+        true => -delta.signum().as_vec3(),
+        false => delta.as_vec3(),
+    }
+}
+
+// gives player two's snake its own colours by swapping in tinted copies of the scene materials
+fn tint_snake_scene(
+    trigger: Trigger<SceneInstanceReady>,
+    segment_query: Query<&SnakeBodySegment>,
+    player_query: Query<&Player>,
+    children_query: Query<&Children>,
+    mut material_query: Query<&mut MeshMaterial3d<StandardMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut tinted: ResMut<TintedMaterials>,
+) {
+    let entity = trigger.entity();
+    let head = segment_query
+        .get(entity)
+        .map_or(entity, |segment| segment.head);
+    if player_query.get(head) != Ok(&Player::Two) {
+        return;
+    }
+
+    for descendant in children_query.iter_descendants(entity) {
+        let Ok(mut material) = material_query.get_mut(descendant) else {
+            continue;
         };
+        if let Some(handle) = tinted.0.get(&material.0.id()) {
+            material.0 = handle.clone();
+            continue;
+        }
 
-        // apply the rotation and scene
-        let (_, _, _, mut transform, mut scene_root) = body_query.get_mut(entity).unwrap();
-        transform.rotation = Quat::from_rotation_y(rotation);
-        scene_root.0 = scene;
+        let Some(mut tinted_material) = materials.get(&material.0).cloned() else {
+            continue;
+        };
+        tinted_material.base_color = tinted_material.base_color.rotate_hue(150.0);
+        let handle = materials.add(tinted_material);
+        tinted.0.insert(material.0.id(), handle.clone());
+        material.0 = handle;
     }
 }
 
 fn grid_direction(first: &GridPosition, second: &GridPosition) -> Dir3 {
-    if second.0.z < first.0.z {
+    let mut delta = second.0 - first.0;
+
+    // neighbours more than one cell apart are joined across the wrap-around seam
+    if delta.x.abs() > 1 {
+        delta.x = -delta.x.signum();
+    }
+    if delta.z.abs() > 1 {
+        delta.z = -delta.z.signum();
+    }
+
+    if delta.z < 0 {
         Dir3::NEG_Z
-    } else if second.0.z > first.0.z {
+    } else if delta.z > 0 {
         Dir3::Z
-    } else if second.0.x < first.0.x {
+    } else if delta.x < 0 {
         Dir3::NEG_X
     } else {
         Dir3::X
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::occupancy::OccupancyPlugin;
+
+    fn app(tail_collision: TailCollision) -> App {
+        let mut app = App::new();
+        app.add_plugins(OccupancyPlugin)
+            .insert_resource(GameRules {
+                tail_collision,
+                ..default()
+            })
+            .init_resource::<GameMode>()
+            .init_resource::<Time>()
+            .init_resource::<Time<Fixed>>()
+            .add_observer(on_turn_snake);
+        app.world_mut().spawn(ArenaSize::new(11, 11));
+        app
+    }
+
+    // spawns a snake over the given cells, head first
+    fn spawn_snake(app: &mut App, cells: &[IVec3], direction: Dir3, growth: usize) -> Entity {
+        let world = app.world_mut();
+        let head = world
+            .spawn((
+                SnakeHead,
+                GridPosition(cells[0]),
+                SnakeDirection(direction),
+                SnakeBodyBuffer(growth),
+            ))
+            .id();
+        let body = cells[1..]
+            .iter()
+            .map(|cell| {
+                world
+                    .spawn((SnakeBodySegment { head }, GridPosition(*cell)))
+                    .id()
+            })
+            .collect();
+        world.entity_mut(head).insert(SnakeBody(body));
+        head
+    }
+
+    fn move_snakes(app: &mut App) {
+        let world = app.world_mut();
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        world.run_system_once(move_snake).unwrap();
+    }
+
+    fn head_translation(app: &mut App, head: Entity) -> Vec3 {
+        let world = app.world_mut();
+        world.run_system_once(interpolate_snake).unwrap();
+        world.get::<Transform>(head).unwrap().translation
+    }
+
+    fn cells(app: &App, head: Entity) -> Vec<IVec3> {
+        let world = app.world();
+        let body = world.get::<SnakeBody>(head).unwrap();
+        std::iter::once(head)
+            .chain(body.iter())
+            .map(|entity| world.get::<GridPosition>(entity).unwrap().0)
+            .collect()
+    }
+
+    fn crashed(app: &App, head: Entity) -> bool {
+        app.world().get::<SnakeCrashed>(head).is_some()
+    }
+
+    // a snake curled into a square, about to move into its tail
+    const SQUARE: [IVec3; 4] = [
+        IVec3::new(0, 0, 0),
+        IVec3::new(1, 0, 0),
+        IVec3::new(1, 0, 1),
+        IVec3::new(0, 0, 1),
+    ];
+
+    #[test]
+    fn strict_tail_blocks_its_cell() {
+        let mut app = app(TailCollision::Strict);
+        let head = spawn_snake(&mut app, &SQUARE, Dir3::Z, 0);
+        move_snakes(&mut app);
+
+        assert!(crashed(&app, head));
+        assert_eq!(cells(&app, head), SQUARE);
+    }
+
+    #[test]
+    fn classic_head_follows_tail() {
+        let mut app = app(TailCollision::Classic);
+        let head = spawn_snake(&mut app, &SQUARE, Dir3::Z, 0);
+        move_snakes(&mut app);
+
+        assert!(!crashed(&app, head));
+        assert_eq!(
+            cells(&app, head),
+            [SQUARE[3], SQUARE[0], SQUARE[1], SQUARE[2]]
+        );
+    }
+
+    #[test]
+    fn classic_growing_tail_blocks_its_cell() {
+        let mut app = app(TailCollision::Classic);
+        let head = spawn_snake(&mut app, &SQUARE, Dir3::Z, 1);
+        move_snakes(&mut app);
+
+        assert!(crashed(&app, head));
+        assert_eq!(cells(&app, head), SQUARE);
+    }
+
+    #[test]
+    fn classic_head_follows_other_tail() {
+        let mut app = app(TailCollision::Classic);
+        let leader = spawn_snake(
+            &mut app,
+            &[
+                IVec3::new(4, 0, 0),
+                IVec3::new(3, 0, 0),
+                IVec3::new(3, 0, 1),
+            ],
+            Dir3::NEG_Z,
+            0,
+        );
+        let follower = spawn_snake(
+            &mut app,
+            &[IVec3::new(2, 0, 1), IVec3::new(1, 0, 1)],
+            Dir3::X,
+            0,
+        );
+        move_snakes(&mut app);
+
+        assert!(!crashed(&app, leader));
+        assert!(!crashed(&app, follower));
+        assert_eq!(cells(&app, follower)[0], IVec3::new(3, 0, 1));
+    }
+
+    #[test]
+    fn classic_shielded_tail_blocks_its_cell() {
+        let mut app = app(TailCollision::Classic);
+        // the leader runs into the wall, and its shield keeps it and its tail in place
+        let leader = spawn_snake(
+            &mut app,
+            &[
+                IVec3::new(5, 0, 0),
+                IVec3::new(4, 0, 0),
+                IVec3::new(4, 0, 1),
+            ],
+            Dir3::X,
+            0,
+        );
+        app.world_mut()
+            .get_mut::<SnakeEffects>(leader)
+            .unwrap()
+            .grant(PowerUpKind::Shield, 1);
+        let follower = spawn_snake(
+            &mut app,
+            &[IVec3::new(3, 0, 1), IVec3::new(2, 0, 1)],
+            Dir3::X,
+            0,
+        );
+        move_snakes(&mut app);
+
+        assert!(!crashed(&app, leader));
+        assert!(crashed(&app, follower));
+    }
+
+    #[test]
+    fn turning_eases_on_from_where_the_head_was() {
+        let mut app = app(TailCollision::Classic);
+        let head = spawn_snake(
+            &mut app,
+            &[IVec3::ZERO, IVec3::new(0, 0, 1)],
+            Dir3::NEG_Z,
+            0,
+        );
+        app.world_mut()
+            .entity_mut(head)
+            .insert((SnakeRotation::default(), Transform::default()));
+        move_snakes(&mut app);
+
+        // halfway out of the first cell
+        let mut timer = app.world_mut().get_mut::<SnakeMoveTimer>(head).unwrap();
+        let half = timer.0.duration() / 2;
+        timer.0.set_elapsed(half);
+        let before = head_translation(&mut app, head);
+        assert_eq!(before, Vec3::new(0.0, 0.0, -0.5));
+
+        // the turn doesn't move the head until the next fixed update
+        app.world_mut().trigger_targets(TurnSnake(Dir3::X), head);
+        assert_eq!(head_translation(&mut app, head), before);
+
+        // which moves it straight away, easing on from where it was
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::ZERO);
+        app.world_mut().run_system_once(move_snake).unwrap();
+        assert_eq!(cells(&app, head)[0], IVec3::new(1, 0, -1));
+        assert_eq!(head_translation(&mut app, head), before);
+
+        // and reaching the new cell when the move is over
+        let mut timer = app.world_mut().get_mut::<SnakeMoveTimer>(head).unwrap();
+        let duration = timer.0.duration();
+        timer.0.set_elapsed(duration);
+        assert_eq!(head_translation(&mut app, head), Vec3::new(1.0, 0.0, -1.0));
+    }
+}
@@ -0,0 +1,75 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    arena::{ArenaSet, ArenaSize},
+    food::{FoodEaten, FoodKind},
+    grid::GridPosition,
+    snake::{SnakeCollided, SnakeHead, SnakeSet},
+};
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(on_food_eaten)
+            .add_observer(on_snake_collided)
+            .add_systems(
+                FixedUpdate,
+                (
+                    count_moves.after(SnakeSet),
+                    track_arena_size.after(ArenaSet),
+                ),
+            );
+    }
+}
+
+// what a snake did during the game, shown once it's over
+#[derive(Component, Default, Debug)]
+pub struct SnakeStats {
+    pub moves: u32,
+    pub food_eaten: HashMap<FoodKind, u32>,
+    // the largest the arena grew to while the snake was alive
+    pub largest_arena: IVec2,
+    // how the snake crashed, if it did
+    pub collision: Option<SnakeCollided>,
+}
+
+impl SnakeStats {
+    pub fn eaten(&self, kind: FoodKind) -> u32 {
+        self.food_eaten.get(&kind).copied().unwrap_or_default()
+    }
+}
+
+fn on_food_eaten(trigger: Trigger<FoodEaten>, mut query: Query<&mut SnakeStats>) {
+    if let Ok(mut stats) = query.get_mut(trigger.entity()) {
+        *stats.food_eaten.entry(trigger.event().0).or_default() += 1;
+    }
+}
+
+fn on_snake_collided(trigger: Trigger<SnakeCollided>, mut query: Query<&mut SnakeStats>) {
+    if let Ok(mut stats) = query.get_mut(trigger.entity()) {
+        stats.collision = Some(*trigger.event());
+    }
+}
+
+// a head changing cells after it spawned is a move, a shield stopping it isn't
+fn count_moves(mut query: Query<(Ref<GridPosition>, &mut SnakeStats), With<SnakeHead>>) {
+    for (grid_position, mut stats) in query.iter_mut() {
+        if grid_position.is_changed() && !grid_position.is_added() {
+            stats.moves += 1;
+        }
+    }
+}
+
+fn track_arena_size(arena_query: Query<&ArenaSize>, mut stats_query: Query<&mut SnakeStats>) {
+    for arena_size in arena_query.iter() {
+        let size = IVec2::new(arena_size.width, arena_size.height);
+        for mut stats in stats_query.iter_mut() {
+            if stats.collision.is_none()
+                && size.element_product() > stats.largest_arena.element_product()
+            {
+                stats.largest_arena = size;
+            }
+        }
+    }
+}
@@ -0,0 +1,433 @@
+// bevy systems routinely trip this lint
+#![allow(clippy::type_complexity)]
+
+use bevy::{app::PluginsState, prelude::*};
+use snake::{
+    arena::{ArenaSize, Obstacle},
+    environment::EnvironmentPlugin,
+    food::{Food, FoodKind, FoodSet},
+    game::{GameState, PlayState},
+    grid::GridPosition,
+    level::Score,
+    power_up::{PowerUp, PowerUpKind, PowerUpSet, SnakeEffects},
+    random::SeedSource,
+    rules::GameRules,
+    snake::{CollisionCause, SnakeBody, SnakeBodyBuffer, SnakeCrashed, TurnSnake},
+    stats::SnakeStats,
+    DespawnGameEntities, SnakeCollided, SnakeHead, SnakeRulesPlugins, SpawnLevel,
+};
+
+// gives up on a move that takes longer than this many fixed updates
+const MAX_UPDATES_PER_MOVE: u32 = 1_000;
+
+// the heads that triggered `SnakeCollided`, in order, with the cause and cell
+#[derive(Resource, Default)]
+struct Collisions(Vec<(Entity, CollisionCause, IVec3)>);
+
+// keeps food and power-ups off the field, so snakes only grow when a test says so
+#[derive(Resource)]
+struct NoPickups;
+
+// the game's rule plugins running headless, one fixed update per app update
+struct Harness {
+    app: App,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let mut app = App::new();
+        app.insert_resource(SeedSource::Fixed(0))
+            .insert_resource(GameRules::default())
+            .add_plugins(MinimalPlugins)
+            .add_plugins(EnvironmentPlugin)
+            .add_plugins(SnakeRulesPlugins)
+            .init_resource::<Collisions>()
+            .add_observer(
+                |trigger: Trigger<SnakeCollided>, mut collisions: ResMut<Collisions>| {
+                    let collision = trigger.event();
+                    collisions
+                        .0
+                        .push((trigger.entity(), collision.cause, collision.cell));
+                },
+            )
+            .add_systems(
+                FixedUpdate,
+                remove_pickups
+                    .after(FoodSet)
+                    .after(PowerUpSet)
+                    .run_if(resource_exists::<NoPickups>),
+            );
+        while app.plugins_state() == PluginsState::Adding {
+            bevy::tasks::tick_global_task_pools_on_main_thread();
+        }
+        app.finish();
+        app.cleanup();
+
+        // entering play spawns the first level
+        while *app.world().resource::<State<GameState>>() != GameState::Play {
+            app.update();
+        }
+        Self { app }
+    }
+
+    fn without_pickups() -> Self {
+        let mut harness = Self::new();
+        harness.app.insert_resource(NoPickups);
+        harness.update(1);
+        harness
+    }
+
+    fn world(&self) -> &World {
+        self.app.world()
+    }
+
+    fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    fn update(&mut self, updates: u32) {
+        for _ in 0..updates {
+            self.app.update();
+        }
+    }
+
+    fn restart(&mut self) {
+        self.world_mut().trigger(DespawnGameEntities);
+        self.world_mut().trigger(SpawnLevel);
+        self.update(1);
+    }
+
+    fn head(&mut self) -> Entity {
+        let world = self.world_mut();
+        world
+            .query_filtered::<Entity, With<SnakeHead>>()
+            .single(world)
+    }
+
+    fn position(&mut self) -> IVec3 {
+        let head = self.head();
+        self.world().get::<GridPosition>(head).unwrap().0
+    }
+
+    fn length(&mut self) -> usize {
+        let head = self.head();
+        self.world().get::<SnakeBody>(head).unwrap().len() + 1
+    }
+
+    fn crashed(&mut self) -> bool {
+        let head = self.head();
+        self.world().get::<SnakeCrashed>(head).is_some()
+    }
+
+    fn arena(&mut self) -> ArenaSize {
+        let world = self.world_mut();
+        world.query::<&ArenaSize>().single(world).clone()
+    }
+
+    fn set_score(&mut self, score: u32) {
+        let head = self.head();
+        self.world_mut().get_mut::<Score>(head).unwrap().0 = score;
+    }
+
+    fn turn(&mut self, direction: Dir3) {
+        let head = self.head();
+        self.world_mut().trigger_targets(TurnSnake(direction), head);
+    }
+
+    // steps until the head has changed cells the given number of times, stopping early on a crash
+    fn advance(&mut self, moves: u32) {
+        for _ in 0..moves {
+            let position = self.position();
+            for _ in 0..MAX_UPDATES_PER_MOVE {
+                self.app.update();
+                if self.crashed() || self.position() != position {
+                    break;
+                }
+            }
+            if self.crashed() {
+                return;
+            }
+        }
+    }
+}
+
+// a magnetised snake with a single food and nothing else on the field
+fn magnet_harness(food: IVec3) -> (Harness, Entity) {
+    let mut harness = Harness::new();
+    harness.update(1);
+    let world = harness.world_mut();
+    let existing: Vec<_> = world
+        .query_filtered::<Entity, Or<(With<Food>, With<PowerUp>)>>()
+        .iter(world)
+        .collect();
+    for entity in existing {
+        world.despawn(entity);
+    }
+    let food = world
+        .spawn((Food(FoodKind::Normal), GridPosition(food)))
+        .id();
+
+    let head = harness.head();
+    harness
+        .world_mut()
+        .get_mut::<SnakeEffects>(head)
+        .unwrap()
+        .grant(PowerUpKind::Magnet, 10);
+    (harness, food)
+}
+
+fn remove_pickups(query: Query<Entity, Or<(With<Food>, With<PowerUp>)>>, mut commands: Commands) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[test]
+fn snake_starts_in_the_centre_heading_up() {
+    let mut harness = Harness::without_pickups();
+    assert_eq!(harness.position(), IVec3::ZERO);
+
+    harness.advance(3);
+    assert_eq!(harness.position(), IVec3::new(0, 0, -3));
+    assert!(!harness.crashed());
+}
+
+#[test]
+fn turns_change_direction() {
+    let mut harness = Harness::without_pickups();
+    harness.turn(Dir3::X);
+    harness.advance(2);
+    harness.turn(Dir3::Z);
+    harness.advance(1);
+
+    assert_eq!(harness.position(), IVec3::new(2, 0, 1));
+}
+
+#[test]
+fn reversing_is_ignored() {
+    let mut harness = Harness::without_pickups();
+    harness.turn(Dir3::Z);
+    harness.advance(1);
+
+    assert_eq!(harness.position(), IVec3::new(0, 0, -1));
+}
+
+#[test]
+fn wall_ends_the_game() {
+    let mut harness = Harness::without_pickups();
+    let head = harness.head();
+    let edge = harness.arena().min().z;
+
+    // the last cell before the wall is still safe
+    harness.advance(edge.unsigned_abs());
+    assert_eq!(harness.position(), IVec3::new(0, 0, edge));
+    assert!(harness.world().resource::<Collisions>().0.is_empty());
+
+    harness.advance(1);
+    assert!(harness.crashed());
+    assert_eq!(harness.position(), IVec3::new(0, 0, edge));
+    let wall = IVec3::new(0, 0, edge - 1);
+    assert_eq!(
+        harness.world().resource::<Collisions>().0,
+        [(head, CollisionCause::Wall, wall)]
+    );
+
+    let stats = harness.world().get::<SnakeStats>(head).unwrap();
+    assert_eq!(stats.moves, edge.unsigned_abs());
+    assert_eq!(stats.collision.unwrap().cause, CollisionCause::Wall);
+
+    // the game is over once the state changes on the next update
+    harness.update(1);
+    assert_eq!(
+        *harness.world().resource::<State<PlayState>>(),
+        PlayState::Over
+    );
+
+    // nothing moves after the crash
+    harness.update(100);
+    assert_eq!(harness.position(), IVec3::new(0, 0, edge));
+    assert_eq!(harness.world().resource::<Collisions>().0.len(), 1);
+}
+
+#[test]
+fn running_into_the_body_ends_the_game() {
+    let mut harness = Harness::without_pickups();
+    let head = harness.head();
+    harness
+        .world_mut()
+        .get_mut::<SnakeBodyBuffer>(head)
+        .unwrap()
+        .0 = 4;
+
+    harness.advance(4);
+    harness.turn(Dir3::X);
+    harness.advance(1);
+    harness.turn(Dir3::Z);
+    harness.advance(1);
+    assert!(!harness.crashed());
+
+    // the cell to the left is the middle of the body
+    harness.turn(Dir3::NEG_X);
+    harness.advance(1);
+    assert!(harness.crashed());
+    assert_eq!(harness.position(), IVec3::new(1, 0, -3));
+    assert_eq!(
+        harness.world().resource::<Collisions>().0,
+        [(head, CollisionCause::OwnBody, IVec3::new(0, 0, -3))]
+    );
+}
+
+#[test]
+fn body_buffer_grows_the_snake_one_segment_per_move() {
+    let mut harness = Harness::without_pickups();
+    let head = harness.head();
+    let initial = GameRules::default().initial_body_length;
+    assert_eq!(harness.length(), 1);
+
+    for length in 2..=initial + 1 {
+        harness.advance(1);
+        assert_eq!(harness.length(), length);
+    }
+    assert_eq!(harness.world().get::<SnakeBodyBuffer>(head).unwrap().0, 0);
+
+    // without anything left to grow into, the snake keeps its length
+    harness.advance(2);
+    assert_eq!(harness.length(), initial + 1);
+
+    harness
+        .world_mut()
+        .get_mut::<SnakeBodyBuffer>(head)
+        .unwrap()
+        .0 = 2;
+    harness.turn(Dir3::X);
+    harness.advance(3);
+    assert!(!harness.crashed());
+    assert_eq!(harness.length(), initial + 3);
+}
+
+#[test]
+fn body_follows_the_head() {
+    let mut harness = Harness::without_pickups();
+    let head = harness.head();
+    harness.advance(3);
+    harness.turn(Dir3::X);
+    harness.advance(1);
+
+    let world = harness.world_mut();
+    let body: Vec<_> = world
+        .get::<SnakeBody>(head)
+        .unwrap()
+        .iter()
+        .map(|segment| world.get::<GridPosition>(segment).unwrap().0)
+        .collect();
+    assert_eq!(body, [IVec3::new(0, 0, -3), IVec3::new(0, 0, -2)]);
+}
+
+#[test]
+fn arena_expands_at_score_thresholds() {
+    let rules = GameRules::default();
+    let mut harness = Harness::without_pickups();
+    let initial = harness.arena();
+    assert_eq!(
+        (initial.width, initial.height),
+        (rules.arena_width, rules.arena_height)
+    );
+
+    // one point short of the threshold keeps the arena as it is
+    let threshold = initial.area() as u32 / rules.expansion_divisor;
+    harness.set_score(threshold - 1);
+    harness.update(1);
+    assert_eq!(harness.arena(), initial);
+
+    harness.set_score(threshold);
+    harness.update(1);
+    let expanded = harness.arena();
+    assert_eq!(expanded.width, initial.width + rules.arena_growth);
+    assert_eq!(expanded.height, initial.height + rules.arena_growth);
+
+    // the next threshold comes from the larger area
+    let next_threshold = expanded.area() as u32 / rules.expansion_divisor;
+    assert!(next_threshold > threshold);
+    harness.set_score(next_threshold - 1);
+    harness.update(1);
+    assert_eq!(harness.arena(), expanded);
+
+    harness.set_score(next_threshold);
+    harness.update(1);
+    assert_eq!(harness.arena().width, expanded.width + rules.arena_growth);
+}
+
+#[test]
+fn restart_spawns_a_fresh_level() {
+    let mut harness = Harness::without_pickups();
+    let head = harness.head();
+    harness.set_score(40);
+    harness.update(1);
+    harness.advance(20);
+    assert!(harness.crashed());
+    assert_ne!(harness.arena().width, GameRules::default().arena_width);
+
+    harness.restart();
+    let new_head = harness.head();
+    assert_ne!(new_head, head);
+    assert!(harness.world().get_entity(head).is_err());
+    assert!(!harness.crashed());
+    assert_eq!(harness.position(), IVec3::ZERO);
+    assert_eq!(harness.length(), 1);
+    assert_eq!(harness.world().get::<Score>(new_head).unwrap().0, 0);
+    assert_eq!(harness.arena().width, GameRules::default().arena_width);
+    assert_eq!(
+        *harness.world().resource::<State<PlayState>>(),
+        PlayState::Running
+    );
+
+    // the new snake plays like the first one did
+    harness.advance(2);
+    assert_eq!(harness.position(), IVec3::new(0, 0, -2));
+    assert_eq!(harness.world().resource::<Collisions>().0.len(), 1);
+    assert_eq!(harness.world().resource::<Collisions>().0[0].0, head);
+}
+
+#[test]
+fn magnet_pulls_food_one_cell_per_move() {
+    let (mut harness, food) = magnet_harness(IVec3::new(2, 0, -2));
+    let head = harness.head();
+
+    harness.advance(1);
+    assert_eq!(
+        harness.world().get::<GridPosition>(food).unwrap().0,
+        IVec3::new(1, 0, -2)
+    );
+    assert_eq!(harness.world().get::<Score>(head).unwrap().0, 0);
+
+    // the food slides onto the head's cell and is eaten there
+    harness.advance(1);
+    assert!(harness.world().get_entity(food).is_err());
+    assert_eq!(harness.world().get::<Score>(head).unwrap().0, 1);
+}
+
+#[test]
+fn magnet_pulls_food_around_obstacles() {
+    let (mut harness, food) = magnet_harness(IVec3::new(2, 0, -2));
+    harness
+        .world_mut()
+        .spawn((Obstacle, GridPosition(IVec3::new(1, 0, -2))));
+
+    harness.advance(1);
+    assert_eq!(
+        harness.world().get::<GridPosition>(food).unwrap().0,
+        IVec3::new(2, 0, -1)
+    );
+}
+
+#[test]
+fn magnet_ignores_food_out_of_reach() {
+    let radius = GameRules::default().magnet_radius;
+    let (mut harness, food) = magnet_harness(IVec3::new(radius + 1, 0, -1));
+
+    harness.advance(1);
+    assert_eq!(
+        harness.world().get::<GridPosition>(food).unwrap().0,
+        IVec3::new(radius + 1, 0, -1)
+    );
+}